@@ -0,0 +1,112 @@
+//! Drives the rain from a custom loop: own input handling, own timing and a clock
+//! overlay composited on top of every frame.
+//!
+//! ```sh
+//! cargo run --example embed
+//! ```
+
+use crossterm::cursor::{self, MoveTo};
+use crossterm::event::{self, Event, KeyCode};
+use crossterm::style::{Color, Print, ResetColor, SetBackgroundColor, SetForegroundColor};
+use crossterm::terminal::{
+  self, DisableLineWrap, EnableLineWrap, EnterAlternateScreen, LeaveAlternateScreen,
+};
+use crossterm::{execute, queue};
+use rmatrix::{Frame, FrameContext, Rain};
+use std::io::{stdout, Write};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+fn main() -> anyhow::Result<()> {
+  let (width, height) = terminal::size()?;
+  let mut rain = Rain::builder()
+    .size(width, height)
+    .set("style", "solid:green")?
+    .set("drops", "60")?
+    .build()?
+    .with_overlay(Box::new(draw_clock));
+
+  terminal::enable_raw_mode()?;
+  // without autowrap a glyph in the bottom right corner can't scroll the screen
+  execute!(
+    stdout(),
+    EnterAlternateScreen,
+    DisableLineWrap,
+    cursor::Hide
+  )?;
+  let result = run(&mut rain);
+  execute!(
+    stdout(),
+    ResetColor,
+    cursor::Show,
+    EnableLineWrap,
+    LeaveAlternateScreen
+  )?;
+  terminal::disable_raw_mode()?;
+
+  result
+}
+
+fn run(rain: &mut Rain) -> anyhow::Result<()> {
+  let mut out = stdout();
+  let mut shown: Option<Frame> = None;
+
+  loop {
+    // `poll` doubles as the frame timer
+    if event::poll(rain.frame_delay())? {
+      match event::read()? {
+        Event::Key(key) if matches!(key.code, KeyCode::Char('q') | KeyCode::Esc) => return Ok(()),
+        Event::Resize(w, h) => {
+          rain.resize(w, h);
+          shown = None;
+        }
+        _ => {}
+      }
+      continue;
+    }
+
+    rain.tick();
+    let frame = rain.render_frame();
+    emit(&frame, shown.as_ref(), &mut out)?;
+    out.flush()?;
+    shown = Some(frame);
+  }
+}
+
+/// `HH:MM:SS` (UTC) in the top right corner.
+fn draw_clock(frame: &mut Frame, _ctx: FrameContext) {
+  let secs = SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .unwrap_or(Duration::ZERO)
+    .as_secs();
+  let text = format!(
+    " {:02}:{:02}:{:02} ",
+    secs / 3600 % 24,
+    secs / 60 % 60,
+    secs % 60
+  );
+
+  let x = frame.width().saturating_sub(text.len() as u16 + 1);
+  frame.put_str(x, 1, &text, Color::White);
+}
+
+/// Writes the cells that differ from the previously shown frame.
+fn emit(frame: &Frame, shown: Option<&Frame>, out: &mut impl Write) -> anyhow::Result<()> {
+  for (y, row) in frame.rows().enumerate() {
+    for (x, cell) in row.iter().enumerate() {
+      let (x, y) = (x as u16, y as u16);
+      if shown.and_then(|shown| shown.get(x, y)) == Some(cell) {
+        continue;
+      }
+
+      queue!(
+        out,
+        MoveTo(x, y),
+        SetBackgroundColor(cell.bg.unwrap_or(Color::Reset)),
+        SetForegroundColor(cell.fg),
+        Print(&cell.glyph)
+      )?;
+    }
+  }
+
+  Ok(())
+}
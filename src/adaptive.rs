@@ -0,0 +1,58 @@
+use rmatrix::Rain;
+use std::time::Duration;
+
+/// Frames averaged before deciding whether to shed drops.
+const WINDOW: u32 = 30;
+
+/// Share of the drops removed each time the window overran.
+const SHED: f32 = 0.1;
+
+/// Sheds drops while frames keep taking longer than their budget, so weak
+/// machines get a thinner but smooth animation instead of a lagging one.
+pub struct Adaptive {
+  min_drops: usize,
+  work: Duration,
+  budget: Duration,
+  frames: u32,
+}
+
+impl Adaptive {
+  pub fn new(min_drops: usize) -> Self {
+    Self {
+      min_drops,
+      work: Duration::ZERO,
+      budget: Duration::ZERO,
+      frames: 0,
+    }
+  }
+
+  /// Records a frame that took `work` out of a `budget`, shedding drops at the end
+  /// of a window whose frames overran on average.
+  pub fn frame(&mut self, rain: &mut Rain, work: Duration, budget: Duration) {
+    self.work += work;
+    self.budget += budget;
+    self.frames += 1;
+    if self.frames < WINDOW {
+      return;
+    }
+
+    let count = rain.config().drops_count;
+    if self.work > self.budget && count > self.min_drops {
+      let shed = ((count as f32 * SHED) as usize).max(1);
+      let reduced = count.saturating_sub(shed).max(self.min_drops);
+      log::warn!(
+        "frames overran their budget ({:?} > {:?} over {} frames), reducing drops {} -> {}",
+        self.work / WINDOW,
+        self.budget / WINDOW,
+        WINDOW,
+        count,
+        reduced
+      );
+      rain.set_drops_count(reduced);
+    }
+
+    self.work = Duration::ZERO;
+    self.budget = Duration::ZERO;
+    self.frames = 0;
+  }
+}
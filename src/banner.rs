@@ -0,0 +1,119 @@
+//! Large block letters for [`Config::banner`](crate::Config::banner).
+
+/// Rows of every letter in [`FONT`].
+pub const HEIGHT: usize = 5;
+
+/// Blank columns between two letters.
+const SPACING: usize = 1;
+
+/// Built-in block font, `#` marks a lit cell. Letters are matched case-insensitively.
+#[rustfmt::skip]
+const FONT: &[(char, [&str; HEIGHT])] = &[
+  ('A', [" ### ", "#   #", "#####", "#   #", "#   #"]),
+  ('B', ["#### ", "#   #", "#### ", "#   #", "#### "]),
+  ('C', [" ####", "#    ", "#    ", "#    ", " ####"]),
+  ('D', ["#### ", "#   #", "#   #", "#   #", "#### "]),
+  ('E', ["#####", "#    ", "#### ", "#    ", "#####"]),
+  ('F', ["#####", "#    ", "#### ", "#    ", "#    "]),
+  ('G', [" ####", "#    ", "#  ##", "#   #", " ####"]),
+  ('H', ["#   #", "#   #", "#####", "#   #", "#   #"]),
+  ('I', ["###", " # ", " # ", " # ", "###"]),
+  ('J', ["  ###", "    #", "    #", "#   #", " ### "]),
+  ('K', ["#   #", "#  # ", "###  ", "#  # ", "#   #"]),
+  ('L', ["#    ", "#    ", "#    ", "#    ", "#####"]),
+  ('M', ["#   #", "## ##", "# # #", "#   #", "#   #"]),
+  ('N', ["#   #", "##  #", "# # #", "#  ##", "#   #"]),
+  ('O', [" ### ", "#   #", "#   #", "#   #", " ### "]),
+  ('P', ["#### ", "#   #", "#### ", "#    ", "#    "]),
+  ('Q', [" ### ", "#   #", "# # #", "#  # ", " ## #"]),
+  ('R', ["#### ", "#   #", "#### ", "#  # ", "#   #"]),
+  ('S', [" ####", "#    ", " ### ", "    #", "#### "]),
+  ('T', ["#####", "  #  ", "  #  ", "  #  ", "  #  "]),
+  ('U', ["#   #", "#   #", "#   #", "#   #", " ### "]),
+  ('V', ["#   #", "#   #", "#   #", " # # ", "  #  "]),
+  ('W', ["#   #", "#   #", "# # #", "## ##", "#   #"]),
+  ('X', ["#   #", " # # ", "  #  ", " # # ", "#   #"]),
+  ('Y', ["#   #", " # # ", "  #  ", "  #  ", "  #  "]),
+  ('Z', ["#####", "   # ", "  #  ", " #   ", "#####"]),
+  ('0', [" ### ", "#  ##", "# # #", "##  #", " ### "]),
+  ('1', [" # ", "## ", " # ", " # ", "###"]),
+  ('2', [" ### ", "#   #", "  ## ", " #   ", "#####"]),
+  ('3', ["#### ", "    #", " ### ", "    #", "#### "]),
+  ('4', ["#   #", "#   #", "#####", "    #", "    #"]),
+  ('5', ["#####", "#    ", "#### ", "    #", "#### "]),
+  ('6', [" ### ", "#    ", "#### ", "#   #", " ### "]),
+  ('7', ["#####", "    #", "   # ", "  #  ", "  #  "]),
+  ('8', [" ### ", "#   #", " ### ", "#   #", " ### "]),
+  ('9', [" ### ", "#   #", " ####", "    #", " ### "]),
+  (' ', ["   ", "   ", "   ", "   ", "   "]),
+  ('.', [" ", " ", " ", " ", "#"]),
+  (',', ["  ", "  ", "  ", " #", "# "]),
+  ('!', ["#", "#", "#", " ", "#"]),
+  ('?', [" ### ", "#   #", "  ## ", "     ", "  #  "]),
+  (':', [" ", "#", " ", "#", " "]),
+  ('-', ["    ", "    ", "####", "    ", "    "]),
+  ('\'', ["#", "#", " ", " ", " "]),
+];
+
+/// Text rasterized with the built-in block font, a grid of lit and unlit cells.
+#[derive(Clone, Debug)]
+pub struct Banner {
+  text: String,
+  rows: [Vec<bool>; HEIGHT],
+}
+
+impl Banner {
+  /// Fails on characters the font has no letter for.
+  pub fn new(text: &str) -> anyhow::Result<Self> {
+    let mut rows: [Vec<bool>; HEIGHT] = Default::default();
+    for (i, c) in text.chars().enumerate() {
+      let upper = c.to_ascii_uppercase();
+      let Some((_, letter)) = FONT.iter().find(|(letter, _)| *letter == upper) else {
+        anyhow::bail!("the banner font has no letter for {:?}", c);
+      };
+
+      for (row, line) in rows.iter_mut().zip(letter) {
+        if i > 0 {
+          row.extend([false; SPACING]);
+        }
+        row.extend(line.chars().map(|cell| cell == '#'));
+      }
+    }
+
+    if rows[0].is_empty() {
+      anyhow::bail!("banner is empty");
+    }
+
+    Ok(Self {
+      text: text.to_owned(),
+      rows,
+    })
+  }
+
+  pub fn text(&self) -> &str {
+    &self.text
+  }
+
+  pub fn width(&self) -> u16 {
+    self.rows[0].len().min(u16::MAX as usize) as u16
+  }
+
+  pub fn height(&self) -> u16 {
+    HEIGHT as u16
+  }
+
+  /// Whether the cell at `col, row` of the banner is part of a letter, `false`
+  /// outside the banner.
+  pub fn is_lit(&self, col: i32, row: i32) -> bool {
+    let (Ok(col), Ok(row)) = (usize::try_from(col), usize::try_from(row)) else {
+      return false;
+    };
+
+    self
+      .rows
+      .get(row)
+      .and_then(|cells| cells.get(col))
+      .copied()
+      .unwrap_or(false)
+  }
+}
@@ -0,0 +1,138 @@
+use crate::crossterm_ext::ColorExt;
+use crossterm::style::Color;
+
+/// Share of a drop's color added to the cell under its head each frame.
+const PAINT_STRENGTH: f32 = 0.2;
+
+/// Persistent RGB grid drop heads paint into, building a slowly fading artwork
+/// underneath the live rain. Paint is blended additively and every cell decays a bit each frame.
+pub struct Canvas {
+  width: u16,
+  height: u16,
+  decay: f32,
+  cells: Vec<[f32; 3]>,
+}
+
+impl Canvas {
+  pub fn new(width: u16, height: u16, decay: f32) -> Self {
+    Self {
+      width,
+      height,
+      decay,
+      cells: vec![[0.0; 3]; width as usize * height as usize],
+    }
+  }
+
+  pub fn width(&self) -> u16 {
+    self.width
+  }
+
+  pub fn height(&self) -> u16 {
+    self.height
+  }
+
+  fn index(&self, x: u16, y: u16) -> Option<usize> {
+    (x < self.width && y < self.height).then(|| y as usize * self.width as usize + x as usize)
+  }
+
+  pub fn paint(&mut self, x: u16, y: u16, (r, g, b): (u8, u8, u8)) {
+    if let Some(i) = self.index(x, y) {
+      for (channel, add) in self.cells[i].iter_mut().zip([r, g, b]) {
+        *channel = (*channel + add as f32 * PAINT_STRENGTH).min(255.0);
+      }
+    }
+  }
+
+  pub fn fade(&mut self) {
+    for channel in self.cells.iter_mut().flatten() {
+      *channel *= self.decay;
+    }
+  }
+
+  /// Adopts a new size in place, keeping the artwork where the old and new grids
+  /// overlap. Cells that are new to the grid start out unpainted.
+  pub fn resize(&mut self, width: u16, height: u16) {
+    let (old, new) = (self.width as usize, width as usize);
+    let rows = self.height.min(height) as usize;
+
+    if new <= old {
+      for y in 1..rows {
+        self.cells.copy_within(y * old..y * old + new, y * new);
+      }
+      self.cells.truncate(rows * new);
+      self.cells.resize(new * height as usize, [0.0; 3]);
+    } else {
+      self.cells.truncate(rows * old);
+      self.cells.resize(new * height as usize, [0.0; 3]);
+      // back to front, so no row is overwritten before it has moved
+      for y in (0..rows).rev() {
+        self.cells.copy_within(y * old..(y + 1) * old, y * new);
+        self.cells[y * new + old..(y + 1) * new].fill([0.0; 3]);
+      }
+    }
+
+    (self.width, self.height) = (width, height);
+  }
+
+  /// Wipes the artwork.
+  pub fn clear(&mut self) {
+    self.cells.fill([0.0; 3]);
+  }
+
+  /// Background for the cell at `x, y` scaled by `brightness`, `None` where nothing was painted.
+  pub fn color_at(&self, x: u16, y: u16, brightness: f32) -> Option<Color> {
+    match self.cells[self.index(x, y)?].map(|c| (c * brightness) as u8) {
+      [0, 0, 0] => None,
+      [r, g, b] => Some(Color::rgb(r, g, b)),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// Paints cell `x, y` with a color unique to its position.
+  fn paint_marked(canvas: &mut Canvas, x: u16, y: u16) {
+    canvas.paint(x, y, (x as u8 * 20 + 5, y as u8 * 20 + 5, 100));
+  }
+
+  #[test]
+  fn resizing_keeps_the_artwork_where_the_grids_overlap() {
+    let mut canvas = Canvas::new(6, 4, 1.0);
+    let mut expected = Canvas::new(6, 4, 1.0);
+    for (x, y) in (0..6).flat_map(|x| (0..4).map(move |y| (x, y))) {
+      paint_marked(&mut canvas, x, y);
+      paint_marked(&mut expected, x, y);
+    }
+
+    for (width, height) in [(3, 4), (9, 2), (9, 7), (1, 1), (0, 3), (6, 4)] {
+      canvas.resize(width, height);
+      // the first pass painted every cell, what was lost on the way stays unpainted
+      expected = {
+        let mut next = Canvas::new(width, height, 1.0);
+        for (x, y) in (0..width).flat_map(|x| (0..height).map(move |y| (x, y))) {
+          if expected.color_at(x, y, 1.0).is_some() {
+            paint_marked(&mut next, x, y);
+          }
+        }
+        next
+      };
+      assert_eq!((canvas.width(), canvas.height()), (width, height));
+      assert_eq!(canvas.cells.len(), width as usize * height as usize);
+      for (x, y) in (0..width).flat_map(|x| (0..height).map(move |y| (x, y))) {
+        assert_eq!(
+          canvas.color_at(x, y, 1.0),
+          expected.color_at(x, y, 1.0),
+          "{},{} at {}x{}",
+          x,
+          y,
+          width,
+          height
+        );
+      }
+    }
+    // nothing survived the width of 0 on the way
+    assert!(canvas.cells.iter().flatten().all(|&c| c == 0.0));
+  }
+}
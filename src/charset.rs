@@ -0,0 +1,59 @@
+use crossterm::queue;
+use crossterm::style::Print;
+use rmatrix::glyph::Glyph;
+use rmatrix::source::GlyphSource;
+use std::collections::HashSet;
+use std::io::Write;
+
+/// Most glyphs listed, the rest of a larger set is only counted.
+const MAX_SHOWN: usize = 2000;
+
+/// Columns the grid wraps at when the output isn't a terminal.
+const DEFAULT_COLUMNS: u16 = 80;
+
+/// Prints every glyph `source` can return as a grid, each distinct glyph once, so
+/// missing fonts (tofu) and unexpected characters show up before a run.
+pub fn print(out: &mut impl Write, source: &dyn GlyphSource) -> anyhow::Result<()> {
+  let mut seen = HashSet::new();
+  let glyphs: Vec<Glyph> = source
+    .glyphs()
+    .into_iter()
+    .filter(|glyph| seen.insert(glyph.clone()))
+    .collect();
+  if glyphs.is_empty() {
+    queue!(out, Print("The glyph source doesn't list its glyphs.\n"))?;
+    out.flush()?;
+    return Ok(());
+  }
+
+  queue!(
+    out,
+    Print(format!(
+      "{} glyphs, up to {} column{} wide:\n\n",
+      glyphs.len(),
+      source.width(),
+      if source.width() == 1 { "" } else { "s" }
+    ))
+  )?;
+
+  let columns = crossterm::terminal::size().map_or(DEFAULT_COLUMNS, |(w, _)| w) as usize;
+  // every glyph takes the widest one's columns and a space, like in a drop
+  let cell = source.width() as usize + 1;
+  let per_row = (columns / cell).max(1);
+  for row in glyphs[..glyphs.len().min(MAX_SHOWN)].chunks(per_row) {
+    for glyph in row {
+      let pad = cell - glyph.width() as usize;
+      queue!(out, Print(glyph), Print(" ".repeat(pad)))?;
+    }
+    queue!(out, Print("\n"))?;
+  }
+
+  if glyphs.len() > MAX_SHOWN {
+    queue!(
+      out,
+      Print(format!("\n... and {} more\n", glyphs.len() - MAX_SHOWN))
+    )?;
+  }
+  out.flush()?;
+  Ok(())
+}
@@ -0,0 +1,868 @@
+use crate::graphics::GraphicsMode;
+use crate::keys::{Action, KeyMap};
+use crate::render::ColorMode;
+use crate::terminal::RenderTarget;
+use crate::themes::Themes;
+use crate::tiles::{TileLayout, MAX_TILES};
+use crate::timing::Timing;
+use anyhow::Context;
+use crossterm::style::Color;
+use rmatrix::config::{format_duration, parse_duration};
+use rmatrix::crossterm_ext::ColorExt;
+use rmatrix::source::{Message, OnEof, Words};
+use rmatrix::Config;
+use smart_default::SmartDefault;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+const USAGE: &str = "\
+Usage: rmatrix [OPTIONS]
+       rmatrix colors              Preview the named colors and some RGB values for --style solid:<color>
+       rmatrix doctor [OPTIONS]    Report what the terminal looks like it supports and warn about options
+                                   it doesn't, with suggestions, instead of running
+
+Options:
+      --drops <N>                 Number of simultaneous drops [default: 80]
+      --dense                     Preset for a solid wall of rain: every column, short gaps, fast,
+                                  shimmering glyphs. Flags after it override its settings
+      --min-length <N>            Shortest drop length [default: 6]
+      --max-length <N>            Longest drop length, exclusive [default: 20]
+      --length <N>                Give every drop the same length instead
+      --length-dist <DIST>        How lengths are picked from the range: `uniform`, `normal:<mean>:<stddev>`
+                                  or `exp:<rate>` (mostly short, a few long) [default: uniform]
+      --style <STYLE>             `rainbow`, `solid:<color>` (name, RRGGBB or `reset` for the terminal's
+                                  default foreground) or `shader:plasma`/`shader:radial`
+                                  (colors following the position and time) [default: rainbow].
+                                  `comet` is a preset for a bright streak with a short, sharply
+                                  fading tail that keeps the colors. Flags after it override it
+      --theme <NAME>              A ready-made look: `classic-green`, `amber-terminal`, `ice-blue`, `rainbow`
+                                  or `cyberpunk`, tab switches to the next one. Colors, glyphs, fade
+                                  curve and head length you set yourself win, wherever they are set
+      --head-length <N>           Bright cells at the front of each drop [default: 1]
+      --head-color <COLOR>        Color of the head cells [default: white]
+      --no-head-bold              Draw the head cells in their color only, not in bold
+      --hue-cycle <DURATION>      Slowly rotate the hue of the whole field over this period, e.g. 20s
+      --delay <DURATION>          Delay between frames, e.g. 100ms or 0.05s [default: 100ms]
+      --delay-ms <MS>             Delay between frames in milliseconds, like --delay <MS>ms
+      --gravity <F>               Rows per frame drops accelerate by, uniform speed when 0 [default: 0]
+      --max-speed <F>             Terminal velocity in rows per frame under gravity [default: 3]
+      --recycle <POLICY>          Where finished drops respawn: `new` column, `same` column or `off` [default: new]
+      --recycle-margin <N>        Finish a drop once its head is N rows below the bottom, cutting
+                                  long, slow tails short instead of waiting for them to leave
+      --exclude <START:END>       Keep columns START..END free of rain, comma separated or repeated
+      --shockwave <F>             Extra speed a shockwave (space) gives every drop [default: 2]
+      --max-spawns-per-frame <N>  Most finished drops replaced per frame, the rest follow later [default: 32]
+      --column-cooldown <N>       Frames a column stays empty after a drop finished in it [default: 0]
+      --column-layers <N>         Drops a column holds at once, up to 4. Every layer behind the front
+                                  one falls slower and dimmer, where they overlap the brighter shows
+                                  [default: 1]
+      --fill <F>                  Share of the screen height covered by rain from the start [default: 0]
+      --start-delay <N>           Max random frames a new drop waits before falling [default: 30]
+      --spawn-above <N>           Start new drops N rows above the screen, so their heads scroll in
+                                  gradually, instead of a few rows down it
+      --chars <GLYPHS>            Custom glyphs, emoji and other grapheme clusters are supported.
+                                  Drops take the width of the widest one, a screen narrower than
+                                  that stays blank
+      --charset <SET:WEIGHT>      Add a charset to a weighted mix, repeat it or separate sets by
+                                  commas: `--charset katakana:0.7 --charset digits:0.25`. SET is
+                                  `katakana`, `digits`, `symbols`, `latin`, `ascii` or the glyphs
+                                  themselves, weights are relative to their sum [default weight: 1]
+      --glyphs <SOURCE>           `matrix` (katakana, digits, mirrored symbols), `words` (random
+                                  words down every column), `connectors` (box-drawing traces),
+                                  `message:<text>`, `weighted:<glyph>=<weight>,...` or `stdin`
+                                  (spell the piped text along the drops) instead of random --chars
+      --on-eof <MODE>             What drops longer than the text piped with `--glyphs stdin` show past
+                                  its end: `loop` it, `random` glyphs or `stop` (blank) [default: loop]
+      --words <FILE>              Like `--glyphs words` with the whitespace separated words in FILE
+      --text-share <F>            Share of drops spelling the --glyphs message or words, the rest show
+                                  random glyphs and are drawn beneath them [default: 1]
+      --stable-glyphs             Glyphs scroll down with their drop instead of staying on their row
+      --motion-blur               Fast drops fade softer and longer, slow ones stay crisp
+      --glitch-fraction <F>       Share of drops whose glyphs shimmer while falling [default: 0]
+      --trail-density <F>         Share of tail cells showing a glyph, the rest stay blank [default: 1]
+      --tail-dither <N>           Let up to N cells at the tip of each tail flicker out, dissolving its end [default: 0]
+      --fade-curve <CURVE>        Trail brightness falloff: `linear`, `quadratic` or `exp` [default: linear]
+      --unfocused-brightness <F>  Brightness while the terminal is unfocused [default: 0.4]
+      --unfocused-fps <F>         Frame rate multiplier while unfocused [default: 0.25]
+      --canvas                    Drop heads paint a fading artwork under the rain, `c` clears it
+      --canvas-decay <F>          Per-frame fade of the canvas [default: 0.98]
+      --tail-alpha <F>            Let the canvas show through drop tails by up to F [default: 0]
+      --accent <COLOR>            Color random cells briefly flash in
+      --erase-color <COLOR>       Leave cells a drop passed over with this background instead of
+                                  blank, e.g. a dim 001500 for a faint wash down the columns
+      --background <COLOR>        Paint the whole screen under the rain, `reset` keeps the
+                                  terminal's own [default: reset]
+      --accent-rate <F>           Share of cells flashing the accent each frame [default: 0.01]
+      --flicker <F>               Share of cells flaring brighter for a single frame, e.g. 0.02 [default: 0]
+      --impact <CHAR>             Splash CHAR for a few frames where a drop hits the bottom, e.g. '*'
+      --vignette <F>              Dim cells toward the screen edges by up to F [default: 0]
+      --scanlines                 Darken the background of every other row for a CRT look
+      --temperature <F>           Warm (up to 1, night mode) or cool (down to -1) color filter [default: 0]
+      --bg-text <FILE>            Show the text (e.g. ASCII art) in FILE with the rain falling around it
+      --bg-pos <X:Y>              Top left corner of --bg-text, centered by default
+      --banner <TEXT>             Show TEXT in big block letters in the middle, with dimmed rain around
+                                  it (letters, digits and . , ! ? : - ')
+      --reveal <TEXT>             Resolve TEXT out of scrambling glyphs in the middle of the screen,
+                                  char by char, e.g. \"ACCESS GRANTED\"
+      --tiles <CxR>               Tile the terminal with C by R independent rains, e.g. 2x2, at
+                                  most 16 along either side
+      --tile-gap <N>              Blank columns and rows between tiles [default: 1]
+      --tile-border <COLOR>       Draw a box around every tile in COLOR, left out while a tile is
+                                  smaller than 3x3
+      --tile-styles <LIST>        Comma separated styles the tiles take in turn, row by row, e.g.
+                                  rainbow,solid:00ff41,shader:plasma [default: --style for all]
+      --virtual-size <WxH>        Run the rain on a fixed W by H grid, centered in the terminal and
+                                  clipped when it doesn't fit, e.g. 120x40 for identical recordings
+      --drift <DURATION>          Against burn-in on always-on displays: shift the whole picture by a
+                                  cell at this interval, within a one cell margin, e.g. 5m
+      --timing <MODE>             `sleep` or `hybrid` (spin-waits frame ends for precise pacing) [default: sleep]
+      --delay-jitter <F>          Lengthen or shorten every frame's wait by a random share up to F, for a
+                                  less mechanical rhythm, e.g. 0.1 [default: 0]
+      --render-to <STREAM>        Draw on `stdout` or `stderr`, keeping the other free for data [default: stdout]
+      --color-mode <MODE>         Color depth to draw in, when detection gets it wrong [default: auto]
+                                    auto       truecolor if $COLORTERM says so, 256 for a *-256color $TERM,
+                                               16 on the Linux console, mono on a dumb one, else truecolor
+                                    truecolor  every color as computed
+                                    256        the nearest of the xterm palette's color cube and grey ramp
+                                    16         the nearest named color, as styled by the terminal's theme
+                                    mono       no colors, only the terminal's default ones
+      --graphics <MODE>           Draw a soft glow under the rain as a picture, in builds with the `graphics`
+                                  feature and on terminals that report their size in pixels [default: off]
+                                    auto       kitty in kitty, WezTerm and Ghostty, sixel on a foot, mlterm
+                                               or contour $TERM, else off
+                                    kitty      the kitty graphics protocol, beneath the text
+                                    sixel      DEC SIXEL (e.g. xterm -ti vt340), glyphs are drawn over it and
+                                               it covers cell backgrounds and the bottom row is left out
+                                    off        cells only
+      --reshuffle <DURATION>      Let the field drain and start over at this interval, e.g. 60s
+      --countdown <DURATION>      Show a big countdown over the rain, exit when it runs out, e.g. 10s
+      --easter-eggs               Some letter keys type a themed message over the rain, try `n`
+      --profile                   Print per-stage frame timings to stderr every second (redirect it, e.g. 2>profile.log)
+      --timing-csv <FILE>         Append frame, draw ms, total ms, drops and size of every frame to FILE
+      --adaptive                  Reduce the number of drops while frames overrun their budget
+      --min-drops <N>             Fewest drops --adaptive reduces to [default: 10]
+      --no-alt-screen             Draw on the main screen instead of the alternate screen buffer
+      --direct-draw               Debugging: draw every drop as it comes instead of emitting only the
+                                  cells that changed since the last frame (flickers on slow terminals)
+      --seed <N>                  Seed for the random generator, random by default
+      --record <FILE>             Record the seed and input events to FILE
+      --replay <FILE>             Replay a recording made with --record
+      --state-file <FILE>         Save the seed and frame count to FILE on exit and resume from
+                                  them on the next start, so the rain carries on where it stopped.
+                                  A missing or unreadable FILE starts afresh
+      --print-config              Print the resolved settings in config file format and exit
+      --show-charset              Print every glyph the rain would be drawn with, after --chars,
+                                  --charset, --glyphs and the config file are applied, and exit
+  -h, --help                      Print help
+
+Every option can also be set in $XDG_CONFIG_HOME/rmatrix/config.toml (`head-length = 2`)
+or through an RMATRIX_* environment variable (RMATRIX_HEAD_LENGTH=2, RMATRIX_CHARSET and
+RMATRIX_DENSITY are aliases for --chars and --drops). Flags override the environment,
+which overrides the config file.
+
+Keys are bound with `key-<action> = \"<key>,...\"`, e.g. `key-quit = \"q,ctrl+c\"`, an empty
+list unbinds the action. Actions: quit [q,esc,ctrl+c], clear-canvas [c], shockwave [space],
+pause [p], next-theme [tab].
+";
+
+/// Prefix of the environment variables that provide defaults, e.g. `RMATRIX_STYLE`.
+const ENV_PREFIX: &str = "RMATRIX_";
+
+/// Environment variable names that differ from the flag they set.
+const ENV_ALIASES: &[(&str, &str)] = &[("charset", "chars"), ("density", "drops")];
+
+/// Flags that don't take a value, `--flag` is short for `--flag=true`.
+/// `--no-flag` is short for `--flag=false`.
+const SWITCHES: &[&str] = &[
+  "canvas",
+  "head-bold",
+  "scanlines",
+  "alt-screen",
+  "profile",
+  "dense",
+  "adaptive",
+  "stable-glyphs",
+  "motion-blur",
+  "easter-eggs",
+  "direct-draw",
+];
+
+/// Everything the command line resolves to: the rain settings plus the options
+/// that only concern the terminal driver.
+#[derive(Clone, SmartDefault)]
+pub struct Options {
+  pub config: Config,
+  /// Write the seed and input events to this file for a later `replay`.
+  pub record: Option<PathBuf>,
+  /// Re-run a log written by `record` instead of reading live input.
+  pub replay: Option<PathBuf>,
+  /// Resume from the seed and frame saved here, and save them again on exit.
+  pub state_file: Option<PathBuf>,
+  pub timing: Timing,
+  /// Random share (`0..1`) every frame's sleep is lengthened or shortened by.
+  pub delay_jitter: f32,
+  /// Stream the rain is drawn on.
+  pub render_to: RenderTarget,
+  /// Color depth the terminal is drawn in, detected by default.
+  pub color_mode: ColorMode,
+  /// Glow drawn under the rain on terminals that show pictures.
+  pub graphics: GraphicsMode,
+  /// File `config.background_text` was read from.
+  pub bg_text: Option<PathBuf>,
+  /// Word list `config.glyphs` was read from.
+  pub words: Option<PathBuf>,
+  /// `config.glyphs` spells what is piped to stdin, read once every option is known.
+  pub glyphs_stdin: bool,
+  /// What drops longer than the piped text show past its end.
+  pub on_eof: OnEof,
+  /// Show a big countdown over the rain and drain the screen and exit when it runs out.
+  pub countdown: Option<Duration>,
+  /// Drain the field and start over with fresh drops at this interval.
+  pub reshuffle: Option<Duration>,
+  /// Unbound letter keys type themed messages over the rain.
+  pub easter_eggs: bool,
+  /// Print per-stage frame timings to stderr every second.
+  pub profile: bool,
+  /// Append a row of timings per frame to this CSV file.
+  pub timing_csv: Option<PathBuf>,
+  /// Shed drops while frames keep overrunning their budget.
+  pub adaptive: bool,
+  /// Fewest drops `adaptive` goes down to.
+  #[default(10)]
+  pub min_drops: usize,
+  /// Draw on the alternate screen buffer so the shell's contents survive.
+  #[default(true)]
+  pub alt_screen: bool,
+  /// Draw drops straight to the terminal instead of diffing whole frames, for debugging.
+  pub direct_draw: bool,
+  /// Bindings of the interactive controls, set through `key-<action>`.
+  pub keys: KeyMap,
+  /// Columns and rows of independent rains tiling the terminal.
+  pub tiles: Option<(u16, u16)>,
+  /// Gap and borders between the tiles.
+  pub tile_layout: TileLayout,
+  /// Styles the tiles take in turn, all keep the configured one when empty.
+  pub tile_styles: Vec<String>,
+  /// Fixed width and height the rain runs at, whatever the terminal's size.
+  pub virtual_size: Option<(u16, u16)>,
+  /// Move the whole picture by a cell at this interval, against burn-in.
+  pub drift: Option<Duration>,
+  /// `--theme`, applied once everything else is known.
+  pub themes: Themes,
+}
+
+impl Options {
+  fn set(&mut self, key: &str, value: &str) -> anyhow::Result<()> {
+    if key == "theme" {
+      return self
+        .themes
+        .select(value)
+        .with_context(|| format!("invalid value for `theme`: {:?}", value));
+    }
+    self.themes.pin(key);
+    if let Some(action) = Action::from_setting(key) {
+      return self
+        .keys
+        .bind(action, value)
+        .with_context(|| format!("invalid value for `{}`: {:?}", key, value));
+    }
+
+    match key {
+      "glyphs" if value == "stdin" => {
+        self.glyphs_stdin = true;
+        self.words = None;
+      }
+      "on-eof" => {
+        self.on_eof = value
+          .parse()
+          .with_context(|| format!("invalid value for `on-eof`: {:?}", value))?
+      }
+      "words" => {
+        let file = std::fs::File::open(value)
+          .with_context(|| format!("failed to read words from {}", value))?;
+        self.config.glyphs = Arc::new(Words::read(file)?);
+        self.words = Some(value.into());
+        self.glyphs_stdin = false;
+      }
+      "chars" | "glyphs" | "charset" => {
+        // whichever glyph setting comes last wins
+        self.words = None;
+        self.glyphs_stdin = false;
+        self.config.set(key, value)?
+      }
+      "bg-text" => {
+        let text = std::fs::read_to_string(value)
+          .with_context(|| format!("failed to read background text from {}", value))?;
+        self.config.background_text = Some(text.lines().map(str::to_owned).collect());
+        self.bg_text = Some(value.into());
+      }
+      "tiles" => {
+        self.tiles = Some(
+          parse_tiles(value).with_context(|| format!("invalid value for `tiles`: {:?}", value))?,
+        )
+      }
+      "virtual-size" => {
+        self.virtual_size = Some(
+          parse_size(value)
+            .with_context(|| format!("invalid value for `virtual-size`: {:?}", value))?,
+        )
+      }
+      "drift" => {
+        self.drift = Some(
+          parse_duration(value)
+            .with_context(|| format!("invalid value for `drift`: {:?}", value))?,
+        )
+      }
+      "tile-gap" => {
+        self.tile_layout.gap = value
+          .parse()
+          .with_context(|| format!("invalid value for `tile-gap`: {:?}", value))?
+      }
+      "tile-border" => {
+        self.tile_layout.border = Some(
+          Color::parse(value)
+            .with_context(|| format!("invalid value for `tile-border`: {:?}", value))?,
+        )
+      }
+      "tile-styles" => {
+        let styles: Vec<String> = value.split(',').map(|s| s.trim().to_owned()).collect();
+        for style in &styles {
+          Config::default()
+            .set("style", style)
+            .with_context(|| format!("invalid value for `tile-styles`: {:?}", style))?;
+        }
+        // the tiles keep their own styles across theme switches
+        self.themes.pin("style");
+        self.tile_styles = styles;
+      }
+      "record" => self.record = Some(value.into()),
+      "replay" => self.replay = Some(value.into()),
+      "state-file" => self.state_file = Some(value.into()),
+      "render-to" => {
+        self.render_to = value
+          .parse()
+          .with_context(|| format!("invalid value for `render-to`: {:?}", value))?
+      }
+      "color-mode" => {
+        self.color_mode = value
+          .parse()
+          .with_context(|| format!("invalid value for `color-mode`: {:?}", value))?
+      }
+      "graphics" => {
+        self.graphics = value
+          .parse()
+          .with_context(|| format!("invalid value for `graphics`: {:?}", value))?
+      }
+      "timing" => {
+        self.timing = value
+          .parse()
+          .with_context(|| format!("invalid value for `timing`: {:?}", value))?
+      }
+      "delay-jitter" => {
+        self.delay_jitter = value
+          .parse()
+          .with_context(|| format!("invalid value for `delay-jitter`: {:?}", value))?
+      }
+      "reshuffle" => {
+        self.reshuffle = Some(
+          parse_duration(value)
+            .with_context(|| format!("invalid value for `reshuffle`: {:?}", value))?,
+        )
+      }
+      "countdown" => {
+        self.countdown = Some(
+          parse_duration(value)
+            .with_context(|| format!("invalid value for `countdown`: {:?}", value))?,
+        )
+      }
+      "easter-eggs" => {
+        self.easter_eggs = value
+          .parse()
+          .with_context(|| format!("invalid value for `easter-eggs`: {:?}", value))?
+      }
+      "profile" => {
+        self.profile = value
+          .parse()
+          .with_context(|| format!("invalid value for `profile`: {:?}", value))?
+      }
+      "timing-csv" => self.timing_csv = Some(value.into()),
+      "adaptive" => {
+        self.adaptive = value
+          .parse()
+          .with_context(|| format!("invalid value for `adaptive`: {:?}", value))?
+      }
+      "min-drops" => {
+        self.min_drops = value
+          .parse()
+          .with_context(|| format!("invalid value for `min-drops`: {:?}", value))?
+      }
+      "alt-screen" => {
+        self.alt_screen = value
+          .parse()
+          .with_context(|| format!("invalid value for `alt-screen`: {:?}", value))?
+      }
+      "direct-draw" => {
+        self.direct_draw = value
+          .parse()
+          .with_context(|| format!("invalid value for `direct-draw`: {:?}", value))?
+      }
+      _ => self.config.set(key, value)?,
+    }
+
+    Ok(())
+  }
+
+  /// Applies the `key = value` lines of a config file, a missing file is not an error.
+  fn load_file(&mut self, path: &Path) -> anyhow::Result<()> {
+    let src = match std::fs::read_to_string(path) {
+      Ok(src) => src,
+      Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+      Err(e) => {
+        return Err(e).with_context(|| format!("failed to read config from {}", path.display()))
+      }
+    };
+
+    for (i, line) in src.lines().enumerate() {
+      let line = line.trim();
+      if line.is_empty() || line.starts_with('#') {
+        continue;
+      }
+
+      let setting = line
+        .split_once('=')
+        .context("expected `key = value`")
+        .and_then(|(key, value)| {
+          let value = value.trim();
+          let value = value
+            .strip_prefix('"')
+            .and_then(|v| v.strip_suffix('"'))
+            .unwrap_or(value);
+          self.set(key.trim(), value)
+        });
+      setting.with_context(|| format!("{}:{}", path.display(), i + 1))?;
+    }
+
+    Ok(())
+  }
+
+  /// Applies the `RMATRIX_*` variables. Values that don't parse or leave the settings
+  /// invalid are reported and skipped, so a stray variable never keeps a
+  /// daemon-launched screensaver from starting.
+  fn load_env(&mut self, vars: impl IntoIterator<Item = (String, String)>) {
+    let mut pending = Vec::new();
+    for (name, value) in vars {
+      let Some(key) = name.strip_prefix(ENV_PREFIX) else {
+        continue;
+      };
+
+      let key = key.to_lowercase().replace('_', "-");
+      let key = ENV_ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == key)
+        .map_or(key.as_str(), |(_, flag)| flag)
+        .to_owned();
+      pending.push((name, key, value));
+    }
+
+    // an invalid config file is left to the final validation to report
+    let checked = self.validate().is_ok();
+    loop {
+      let before = pending.len();
+      let mut failed = Vec::new();
+      for (name, key, value) in pending {
+        let mut trial = self.clone();
+        let result =
+          trial
+            .set(&key, &value)
+            .and_then(|_| if checked { trial.validate() } else { Ok(()) });
+        match result {
+          Ok(()) => *self = trial,
+          Err(e) => failed.push((name, key, value, e)),
+        }
+      }
+
+      // values only valid along with others, e.g. a min-length above the default
+      // max-length, are retried once those are in
+      if failed.len() == before {
+        for (name, _, _, e) in failed {
+          eprintln!("warning: ignoring {}: {:#}", name, e);
+        }
+        return;
+      }
+      pending = failed
+        .into_iter()
+        .map(|(name, key, value, _)| (name, key, value))
+        .collect();
+    }
+  }
+
+  /// Spells the text piped to stdin along the drops, empty input falls back to
+  /// random glyphs.
+  fn read_stdin(&mut self) -> anyhow::Result<()> {
+    let text = std::io::read_to_string(std::io::stdin()).context("failed to read stdin")?;
+    if text.trim().is_empty() {
+      eprintln!("warning: stdin is empty, using random glyphs");
+      self.config.glyphs = Config::default().glyphs;
+    } else {
+      self.config.glyphs = Arc::new(Message::read(text.as_bytes())?.on_eof(self.on_eof));
+    }
+
+    Ok(())
+  }
+
+  /// Every setting in the format of the config file.
+  pub fn to_toml(&self) -> String {
+    let mut entries = self.config.entries();
+    if let Some(path) = &self.words {
+      entries.push(("words", path.display().to_string()));
+    }
+    if self.glyphs_stdin {
+      entries.push(("glyphs", "stdin".to_owned()));
+    }
+    entries.push(("on-eof", self.on_eof.to_string()));
+    if let Some(theme) = self.themes.current() {
+      entries.push(("theme", theme.name.to_owned()));
+    }
+    if let Some(path) = &self.state_file {
+      entries.push(("state-file", path.display().to_string()));
+    }
+    if let Some(path) = &self.bg_text {
+      entries.push(("bg-text", path.display().to_string()));
+    }
+    if let Some((columns, rows)) = self.tiles {
+      entries.push(("tiles", format!("{}x{}", columns, rows)));
+    }
+    entries.push(("tile-gap", self.tile_layout.gap.to_string()));
+    if let Some(border) = self.tile_layout.border {
+      let border = match border.to_rgb() {
+        Some((r, g, b)) => format!("{:02X}{:02X}{:02X}", r, g, b),
+        None => "reset".to_owned(),
+      };
+      entries.push(("tile-border", border));
+    }
+    if !self.tile_styles.is_empty() {
+      entries.push(("tile-styles", self.tile_styles.join(",")));
+    }
+    if let Some((width, height)) = self.virtual_size {
+      entries.push(("virtual-size", format!("{}x{}", width, height)));
+    }
+    if let Some(drift) = self.drift {
+      entries.push(("drift", format_duration(drift)));
+    }
+    entries.push(("timing", self.timing.to_string()));
+    entries.push(("delay-jitter", self.delay_jitter.to_string()));
+    entries.push(("render-to", self.render_to.to_string()));
+    entries.push(("color-mode", self.color_mode.to_string()));
+    entries.push(("graphics", self.graphics.to_string()));
+    if let Some(countdown) = self.countdown {
+      entries.push(("countdown", format_duration(countdown)));
+    }
+    if let Some(reshuffle) = self.reshuffle {
+      entries.push(("reshuffle", format_duration(reshuffle)));
+    }
+    entries.extend([
+      ("easter-eggs", self.easter_eggs.to_string()),
+      ("profile", self.profile.to_string()),
+    ]);
+    if let Some(path) = &self.timing_csv {
+      entries.push(("timing-csv", path.display().to_string()));
+    }
+    entries.extend([
+      ("adaptive", self.adaptive.to_string()),
+      ("min-drops", self.min_drops.to_string()),
+      ("alt-screen", self.alt_screen.to_string()),
+      ("direct-draw", self.direct_draw.to_string()),
+    ]);
+    entries.extend(Action::ALL.map(|action| (action.setting(), self.keys.keys(action))));
+
+    entries
+      .into_iter()
+      .map(|(key, value)| {
+        // numbers and booleans are bare, everything else is quoted
+        if value.parse::<f64>().is_ok() || value.parse::<bool>().is_ok() {
+          format!("{} = {}\n", key, value)
+        } else {
+          format!("{} = \"{}\"\n", key, value)
+        }
+      })
+      .collect()
+  }
+
+  fn validate(&self) -> anyhow::Result<()> {
+    if self.record.is_some() && self.replay.is_some() {
+      anyhow::bail!("`record` and `replay` can't be used together");
+    }
+    if self.state_file.is_some() {
+      // a resumed run doesn't start where a recording of it does
+      if self.record.is_some() {
+        anyhow::bail!("`record` can't be used with `state-file`");
+      }
+      if self.replay.is_some() {
+        anyhow::bail!("`replay` can't be used with `state-file`");
+      }
+    }
+    if self.adaptive {
+      // drops are shed on the timings of the machine, which the log doesn't hold
+      if self.record.is_some() {
+        anyhow::bail!("`record` can't be used with `adaptive`");
+      }
+      if self.replay.is_some() {
+        anyhow::bail!("`replay` can't be used with `adaptive`");
+      }
+    }
+    if !(0.0..1.0).contains(&self.delay_jitter) {
+      anyhow::bail!("`delay-jitter` must be at least 0 and below 1");
+    }
+    if self.profile && self.render_to == RenderTarget::Stderr {
+      anyhow::bail!("`profile` prints to stderr, it can't be used with `render-to = stderr`");
+    }
+    if self.graphics != GraphicsMode::Off {
+      if !cfg!(feature = "graphics") {
+        anyhow::bail!(
+          "`graphics` needs a build with the `graphics` feature: cargo build --features graphics"
+        );
+      }
+      // the glow is drawn with whole frames of the main loop
+      if self.direct_draw {
+        anyhow::bail!("`direct-draw` can't be used with `graphics`");
+      }
+      if self.tiles.is_some() {
+        anyhow::bail!("`tiles` can't be used with `graphics`");
+      }
+    }
+    if self.drift.is_some() {
+      // the picture is moved around as a whole frame
+      let conflicting = [
+        ("direct-draw", self.direct_draw),
+        ("tiles", self.tiles.is_some()),
+        ("virtual-size", self.virtual_size.is_some()),
+      ];
+      if let Some((flag, _)) = conflicting.iter().find(|(_, set)| *set) {
+        anyhow::bail!("`{}` can't be used with `drift`", flag);
+      }
+    }
+    if self.virtual_size.is_some() {
+      // the virtual frame is placed in the terminal as a whole
+      if self.direct_draw {
+        anyhow::bail!("`direct-draw` can't be used with `virtual-size`");
+      }
+      if self.tiles.is_some() {
+        anyhow::bail!("`tiles` can't be used with `virtual-size`");
+      }
+    }
+    if self.tiles.is_some() {
+      // these drive a single rain from the main loop, tiles run their own
+      let single = [
+        ("record", self.record.is_some()),
+        ("replay", self.replay.is_some()),
+        ("countdown", self.countdown.is_some()),
+        ("reshuffle", self.reshuffle.is_some()),
+        ("profile", self.profile),
+        ("timing-csv", self.timing_csv.is_some()),
+        ("adaptive", self.adaptive),
+        ("state-file", self.state_file.is_some()),
+      ];
+      if let Some((flag, _)) = single.iter().find(|(_, set)| *set) {
+        anyhow::bail!("`{}` can't be used with `tiles`", flag);
+      }
+    }
+
+    self.config.validate()
+  }
+}
+
+pub enum Command {
+  Run(Box<Options>),
+  /// Print the resolved settings instead of running.
+  PrintConfig(Box<Options>),
+  /// Print the glyphs the rain would be drawn with instead of running.
+  ShowCharset(Box<Options>),
+  /// Print color swatches instead of running.
+  Colors,
+  /// Report the terminal's capabilities and problems with the options instead of running.
+  Doctor(Box<Options>),
+  Help,
+}
+
+pub fn parse_args(args: impl IntoIterator<Item = String>) -> anyhow::Result<Command> {
+  let mut args = args.into_iter().peekable();
+  if args.next_if(|arg| arg == "colors").is_some() {
+    if let Some(arg) = args.next() {
+      anyhow::bail!("unexpected argument `{}` after `colors`", arg);
+    }
+    return Ok(Command::Colors);
+  }
+  let doctor = args.next_if(|arg| arg == "doctor").is_some();
+
+  let mut options = Options::default();
+  if let Some(path) = config_path() {
+    options.load_file(&path)?;
+  }
+  options.load_env(std::env::vars());
+
+  let mut print_config = false;
+  let mut show_charset = false;
+
+  while let Some(arg) = args.next() {
+    if arg == "-h" || arg == "--help" {
+      return Ok(Command::Help);
+    }
+    if arg == "--print-config" {
+      print_config = true;
+      continue;
+    }
+    if arg == "--show-charset" {
+      show_charset = true;
+      continue;
+    }
+
+    let Some(flag) = arg.strip_prefix("--") else {
+      anyhow::bail!("unexpected argument `{}`\n\n{}", arg, USAGE);
+    };
+
+    let (key, value) = match flag.split_once('=') {
+      Some((key, value)) => (key, value.to_owned()),
+      None if SWITCHES.contains(&flag) => (flag, "true".to_owned()),
+      None
+        if flag
+          .strip_prefix("no-")
+          .is_some_and(|switch| SWITCHES.contains(&switch)) =>
+      {
+        (&flag[3..], "false".to_owned())
+      }
+      None => match args.next() {
+        Some(value) => (flag, value),
+        None => anyhow::bail!("`--{}` expects a value", flag),
+      },
+    };
+
+    options.set(key, &value)?;
+  }
+
+  options
+    .themes
+    .apply(|key, value| options.config.set(key, value))?;
+  options.validate()?;
+  if doctor {
+    return Ok(Command::Doctor(Box::new(options)));
+  }
+  if print_config {
+    return Ok(Command::PrintConfig(Box::new(options)));
+  }
+  if options.glyphs_stdin {
+    options.read_stdin()?;
+  }
+  if show_charset {
+    Ok(Command::ShowCharset(Box::new(options)))
+  } else {
+    Ok(Command::Run(Box::new(options)))
+  }
+}
+
+/// `<width>x<height>`, e.g. `120x40`.
+fn parse_size(s: &str) -> anyhow::Result<(u16, u16)> {
+  let (width, height) = s.split_once('x').context("expected `<width>x<height>`")?;
+  let (width, height) = (width.parse()?, height.parse()?);
+  if width == 0 || height == 0 {
+    anyhow::bail!("the size must be at least 1x1");
+  }
+
+  Ok((width, height))
+}
+
+/// `<columns>x<rows>`, e.g. `2x2`.
+fn parse_tiles(s: &str) -> anyhow::Result<(u16, u16)> {
+  let (columns, rows) = s.split_once('x').context("expected `<columns>x<rows>`")?;
+  let (columns, rows) = (columns.parse()?, rows.parse()?);
+  if !(1..=MAX_TILES).contains(&columns) || !(1..=MAX_TILES).contains(&rows) {
+    anyhow::bail!(
+      "tiles need at least one and at most {} columns and rows",
+      MAX_TILES
+    );
+  }
+
+  Ok((columns, rows))
+}
+
+/// `$XDG_CONFIG_HOME/rmatrix/config.toml`, falling back to `~/.config`.
+fn config_path() -> Option<PathBuf> {
+  let dir = std::env::var_os("XDG_CONFIG_HOME")
+    .map(PathBuf::from)
+    .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+
+  Some(dir.join("rmatrix").join("config.toml"))
+}
+
+pub fn print_help() {
+  print!("{}", USAGE);
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn env(vars: &[(&str, &str)]) -> Vec<(String, String)> {
+    vars
+      .iter()
+      .map(|(name, value)| (name.to_string(), value.to_string()))
+      .collect()
+  }
+
+  #[test]
+  fn invalid_env_values_fall_back_to_the_defaults() {
+    let mut options = Options::default();
+    options.load_env(env(&[
+      ("RMATRIX_GLITCH_FRACTION", "5"),
+      ("RMATRIX_HEAD_LENGTH", "two"),
+      ("RMATRIX_DENSITY", "7"),
+    ]));
+
+    let default = Config::default();
+    assert_eq!(options.config.glitch_fraction, default.glitch_fraction);
+    assert_eq!(options.config.head_length, default.head_length);
+    assert_eq!(options.config.drops_count, 7);
+    options.validate().unwrap();
+  }
+
+  #[test]
+  fn env_values_only_valid_together_are_kept() {
+    let mut options = Options::default();
+    // the min-length alone is above the default max-length
+    options.load_env(env(&[
+      ("RMATRIX_MIN_LENGTH", "40"),
+      ("RMATRIX_MAX_LENGTH", "50"),
+    ]));
+
+    assert_eq!(options.config.drop_length_range, 40..50);
+  }
+
+  #[test]
+  fn adaptive_runs_are_neither_recorded_nor_replayed() {
+    for flag in ["record", "replay"] {
+      let mut options = Options::default();
+      options.set("adaptive", "true").unwrap();
+      options.set(flag, "rain.log").unwrap();
+      assert!(options.validate().is_err(), "`{}` is accepted", flag);
+    }
+
+    let mut options = Options::default();
+    options.set("adaptive", "true").unwrap();
+    options.validate().unwrap();
+  }
+
+  #[test]
+  fn walls_stay_within_bounds() {
+    let mut options = Options::default();
+    options.set("tiles", "16x16").unwrap();
+    for tiles in ["0x2", "17x1", "2x300", "300x300"] {
+      assert!(
+        options.set("tiles", tiles).is_err(),
+        "`{}` is accepted",
+        tiles
+      );
+    }
+    options.set("tile-styles", "rainbow,solid:00ff41").unwrap();
+    assert!(options.set("tile-styles", "rainbow,plaid").is_err());
+  }
+}
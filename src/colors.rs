@@ -0,0 +1,53 @@
+use crossterm::queue;
+use crossterm::style::{Color, Print, ResetColor, SetForegroundColor};
+use rmatrix::crossterm_ext::{hsv_to_rgb, ColorExt, NAMED_COLORS};
+use std::io::Write;
+
+/// Hues of the sample RGB swatches, in degrees.
+const HUES: [f32; 12] = [
+  0.0, 30.0, 60.0, 90.0, 120.0, 150.0, 180.0, 210.0, 240.0, 270.0, 300.0, 330.0,
+];
+
+/// Brightness of the bright and the dark shade of every sample hue.
+const SHADES: [f32; 2] = [1.0, 0.55];
+
+/// A block of `color` followed by `label`.
+fn swatch(out: &mut impl Write, color: Color, label: &str) -> anyhow::Result<()> {
+  queue!(
+    out,
+    SetForegroundColor(color),
+    Print("████ "),
+    ResetColor,
+    Print(label)
+  )?;
+  Ok(())
+}
+
+/// Prints a swatch of every named color and of a spread of RGB values, each labeled
+/// with the value `--style solid:<color>` takes for it.
+pub fn print(out: &mut impl Write) -> anyhow::Result<()> {
+  queue!(out, Print("Named colors:\n"))?;
+  for (name, color) in NAMED_COLORS {
+    swatch(out, color, name)?;
+    queue!(out, Print("\n"))?;
+  }
+
+  queue!(out, Print("\nRGB colors:\n"))?;
+  for hue in HUES {
+    for (i, value) in SHADES.into_iter().enumerate() {
+      if i > 0 {
+        queue!(out, Print("   "))?;
+      }
+      let (r, g, b) = hsv_to_rgb(hue, 1.0, value);
+      swatch(
+        out,
+        Color::rgb(r, g, b),
+        &format!("{:02x}{:02x}{:02x}", r, g, b),
+      )?;
+    }
+    queue!(out, Print("\n"))?;
+  }
+
+  out.flush()?;
+  Ok(())
+}
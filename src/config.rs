@@ -0,0 +1,700 @@
+use crate::banner::Banner;
+use crate::crossterm_ext::ColorExt;
+use crate::drop::FadeCurve;
+use crate::glyph::{get_all_unicode_chars, Charset};
+use crate::rain::{LengthDistribution, RainStyle, RecyclePolicy};
+use crate::reveal::Reveal;
+use crate::shader;
+use crate::source::{Authentic, Connectors, GlyphSource, Message, Mixed, Random, Weighted, Words};
+use crate::theme::{Theme, THEMES};
+use anyhow::Context;
+use crossterm::style::Color;
+use smart_default::SmartDefault;
+use std::ops::Range;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Most drops [`Config::column_layers`] stacks in a column, the ones further back
+/// would be too dim and slow to notice.
+const MAX_COLUMN_LAYERS: u8 = 4;
+
+#[derive(Clone, SmartDefault)]
+pub struct Config {
+  #[default(80)]
+  pub drops_count: usize,
+  /// One drop in every column instead of [`Config::drops_count`], see the `dense` preset.
+  pub dense: bool,
+  /// Lengths drops are picked from, the end is exclusive. An empty `n..n` range
+  /// fixes every drop to length `n`. Drops are never longer than the screen is
+  /// high, longer lengths are cut to the height.
+  #[default(6..20)]
+  pub drop_length_range: Range<u8>,
+  #[default(LengthDistribution::Uniform)]
+  pub length_distribution: LengthDistribution,
+  #[default(RainStyle::Rainbow)]
+  pub style: RainStyle,
+  #[default(Duration::from_millis(100))]
+  pub frame_delay: Duration,
+  /// Number of bright [`Config::head_color`] cells at the front of each drop.
+  #[default(1)]
+  pub head_length: u8,
+  /// Draw the bright head cells in bold, which some terminals need to make them stand out.
+  #[default(true)]
+  pub head_bold: bool,
+  /// Color of the head cells, whatever the color of the drop.
+  #[default(Color::White)]
+  pub head_color: Color,
+  /// Period of a full hue rotation of the whole field, disabled when unset.
+  pub hue_cycle: Option<Duration>,
+  /// Rows per frame drops speed up by every frame, uniform speed when 0.
+  pub gravity: f32,
+  /// Terminal velocity (rows per frame) of drops under gravity.
+  #[default(3.0)]
+  pub max_speed: f32,
+  /// What replaces a drop that left the screen.
+  #[default(RecyclePolicy::NewColumn)]
+  pub recycle: RecyclePolicy,
+  /// Rows below the bottom a drop's head may fall before the drop is finished, even
+  /// with its tail still on screen. `None` waits until the tail has left too.
+  pub recycle_margin: Option<u16>,
+  /// Frames a column stays empty after a drop finished in it.
+  pub column_cooldown: u32,
+  /// Drops a column holds at once, each layer behind the front one slower and dimmer.
+  #[default(1)]
+  pub column_layers: u8,
+  /// Most finished drops replaced in one frame, the rest wait for the next frames.
+  #[default(32)]
+  pub max_spawns_per_frame: usize,
+  /// Column ranges no drop spawns in, left free for static content.
+  pub excluded_columns: Vec<Range<u16>>,
+  /// Extra speed (as a multiple of the normal one) a shockwave gives every drop.
+  #[default(2.0)]
+  pub shockwave: f32,
+  /// Share of the screen height (`0..=1`) the drops cover from the first frame.
+  pub fill: f32,
+  /// Upper bound (in frames) of the random delay before a new drop starts falling.
+  #[default(30)]
+  pub max_start_delay: u8,
+  /// Rows above the top new drops start at, so their heads scroll in over that many
+  /// rows of falling. `None` starts them a few rows down the screen.
+  pub spawn_above: Option<u16>,
+  /// Where the glyphs of the drops come from, for the [`Config::text_share`] of them.
+  #[default(Arc::new(Random::new(Charset::from_chars(get_all_unicode_chars()))))]
+  pub glyphs: Arc<dyn GlyphSource>,
+  /// Share of drops (`0..=1`) taking their glyphs from [`Config::glyphs`], the others
+  /// show random glyphs around them. Where the two overlap, the `glyphs` drop is drawn
+  /// on top, so a message or word isn't broken up by random glyphs.
+  #[default(1.0)]
+  pub text_share: f32,
+  /// The `chars`/`glyphs` setting `glyphs` was built from, for [`Config::entries`].
+  glyphs_setting: Option<(&'static str, String)>,
+  /// Name of the built-in shader `style` uses, for [`Config::entries`].
+  shader_setting: Option<String>,
+  /// Every drop carries its own glyphs down with it, see [`crate::drop::DrawContext::stable_glyphs`].
+  pub stable_glyphs: bool,
+  /// Faster drops are dimmer with longer tails, slower ones brighter and crisper.
+  pub motion_blur: bool,
+  /// Share of drops (`0..=1`) whose glyphs shimmer while they fall.
+  pub glitch_fraction: f32,
+  /// Share of the cells (`0..=1`) of a drop's tail that show a glyph, the rest are holes.
+  #[default(1.0)]
+  pub trail_density: f32,
+  /// Up to this many cells at the tip of every tail flicker between their faint color
+  /// and blank, for a dissolving rather than a clean end. Each drop picks its own depth.
+  pub tail_dither: u8,
+  /// Shape of the brightness falloff along the trails.
+  #[default(FadeCurve::Linear)]
+  pub fade_curve: FadeCurve,
+  /// Brightness multiplier applied while the terminal is unfocused.
+  #[default(0.4)]
+  pub unfocused_brightness: f32,
+  /// Frame rate multiplier applied while the terminal is unfocused.
+  #[default(0.25)]
+  pub unfocused_fps: f32,
+  /// Let drop heads paint into a persistent, slowly fading canvas under the rain.
+  pub canvas: bool,
+  /// Per-frame multiplier applied to the canvas colors.
+  #[default(0.98)]
+  pub canvas_decay: f32,
+  /// Color a few random cells flash in instead of their trail color.
+  pub accent_color: Option<Color>,
+  /// Background cells are left with once a drop has passed over them, instead of
+  /// the terminal's. Meant to be dim, columns keep a faint wash of the rain.
+  pub erase_color: Option<Color>,
+  /// Background of the whole screen under the rain, the terminal's own when `Reset`.
+  #[default(Color::Reset)]
+  pub background: Color,
+  /// Share of drawn cells (`0..=1`) that flare toward white for a single frame, from
+  /// whatever brightness the fade gave them.
+  pub flicker_rate: f32,
+  /// Share of drawn cells (`0..=1`) that use the accent color each frame.
+  #[default(0.01)]
+  pub accent_rate: f32,
+  /// Glyph of a short splash where a drop's head hits the bottom row, in the accent
+  /// color or white. No splashes when `None`.
+  pub impact: Option<char>,
+  /// How much dimmer (`0..=1`) cells get toward the screen edges.
+  pub vignette: f32,
+  /// Darken the background of every other row, like the scanlines of a CRT.
+  pub scanlines: bool,
+  /// Transparency (`0..=1`) of the tail end of the drops over a painted background,
+  /// fading to opaque at the head.
+  pub tail_alpha: f32,
+  /// Warm (`> 0`, toward red) or cool (`< 0`, toward blue) filter over every color, within `-1..=1`.
+  pub temperature: f32,
+  /// Static text (e.g. ASCII art) the rain falls around, its non-space cells are never
+  /// drawn over.
+  pub background_text: Option<Vec<String>>,
+  /// Top left corner of `background_text`, centered when unset.
+  pub background_text_position: Option<(u16, u16)>,
+  /// Big block letters centered on screen, the rain skips their cells and is dimmed
+  /// around them. Clipped on both sides when wider than the screen.
+  pub banner: Option<Banner>,
+  /// A line of text in the middle of the screen that resolves out of random glyphs
+  /// over the first seconds, while the rain falls around it. Clipped on both sides
+  /// when wider than the screen.
+  pub reveal: Option<Reveal>,
+  /// Seed for every random choice, picked at random when unset.
+  pub seed: Option<u64>,
+}
+
+impl Config {
+  /// Applies a single `key = value` setting, keys match the long CLI flag names.
+  pub fn set(&mut self, key: &str, value: &str) -> anyhow::Result<()> {
+    self
+      .apply(key, value)
+      .with_context(|| format!("invalid value for `{}`: {:?}", key, value))
+  }
+
+  fn apply(&mut self, key: &str, value: &str) -> anyhow::Result<()> {
+    match key {
+      "drops" => self.drops_count = value.parse()?,
+      "dense" => {
+        self.dense = value.parse()?;
+        if self.dense {
+          self.apply_dense_preset();
+        }
+      }
+      "min-length" => self.drop_length_range.start = value.parse()?,
+      "max-length" => self.drop_length_range.end = value.parse()?,
+      "length" => {
+        let length = value.parse()?;
+        self.drop_length_range = length..length;
+      }
+      "length-dist" => self.length_distribution = parse_length_distribution(value)?,
+      "style" if value == "comet" => self.apply_comet_preset(),
+      "style" => {
+        self.style = parse_style(value)?;
+        self.shader_setting = value.strip_prefix("shader:").map(str::to_owned);
+      }
+      "delay" => self.frame_delay = parse_duration(value)?,
+      "delay-ms" => self.frame_delay = Duration::from_millis(value.parse()?),
+      "head-length" => self.head_length = value.parse()?,
+      "head-bold" => self.head_bold = value.parse()?,
+      "head-color" => self.head_color = Color::parse(value)?,
+      "hue-cycle" => self.hue_cycle = Some(parse_duration(value)?),
+      "gravity" => self.gravity = value.parse()?,
+      "max-speed" => self.max_speed = value.parse()?,
+      "recycle" => self.recycle = parse_recycle(value)?,
+      "recycle-margin" => self.recycle_margin = Some(value.parse()?),
+      "max-spawns-per-frame" => self.max_spawns_per_frame = value.parse()?,
+      "column-cooldown" => self.column_cooldown = value.parse()?,
+      "column-layers" => self.column_layers = value.parse()?,
+      "exclude" => self.excluded_columns.extend(parse_columns(value)?),
+      "shockwave" => self.shockwave = value.parse()?,
+      "fill" => self.fill = value.parse::<f32>()?.clamp(0.0, 1.0),
+      "start-delay" => self.max_start_delay = value.parse()?,
+      "spawn-above" => self.spawn_above = Some(value.parse()?),
+      "chars" => {
+        self.glyphs = Arc::new(Random::new(Charset::parse(value)?));
+        self.glyphs_setting = Some(("chars", value.to_owned()));
+      }
+      "charset" => {
+        // every `charset` adds to the mix, `chars` and `glyphs` start over
+        let mix = match &self.glyphs_setting {
+          Some(("charset", mix)) => format!("{},{}", mix, value),
+          _ => value.to_owned(),
+        };
+        self.glyphs = Arc::new(Mixed::parse(&mix)?);
+        self.glyphs_setting = Some(("charset", mix));
+      }
+      "glyphs" => {
+        self.glyphs = parse_glyphs(value)?;
+        self.glyphs_setting = Some(("glyphs", value.to_owned()));
+      }
+      "stable-glyphs" => self.stable_glyphs = value.parse()?,
+      "text-share" => self.text_share = value.parse()?,
+      "motion-blur" => self.motion_blur = value.parse()?,
+      "glitch-fraction" => self.glitch_fraction = value.parse()?,
+      "trail-density" => self.trail_density = value.parse()?,
+      "tail-dither" => self.tail_dither = value.parse()?,
+      "fade-curve" => self.fade_curve = parse_fade_curve(value)?,
+      "unfocused-brightness" => self.unfocused_brightness = value.parse()?,
+      "unfocused-fps" => self.unfocused_fps = value.parse()?,
+      "canvas" => self.canvas = value.parse()?,
+      "canvas-decay" => self.canvas_decay = value.parse()?,
+      "accent" => self.accent_color = Some(Color::parse(value)?),
+      "erase-color" => self.erase_color = Some(Color::parse(value)?),
+      "background" => self.background = Color::parse(value)?,
+      "accent-rate" => self.accent_rate = value.parse()?,
+      "flicker" => self.flicker_rate = value.parse()?,
+      "impact" => self.impact = Some(value.parse()?),
+      "vignette" => self.vignette = value.parse()?,
+      "scanlines" => self.scanlines = value.parse()?,
+      "tail-alpha" => self.tail_alpha = value.parse()?,
+      "temperature" => self.temperature = value.parse()?,
+      "bg-pos" => {
+        let (x, y) = value.split_once(':').context("expected `x:y`")?;
+        self.background_text_position = Some((x.trim().parse()?, y.trim().parse()?));
+      }
+      "theme" => {
+        for (key, value) in THEMES[Theme::position(value)?].settings {
+          self.apply(key, value)?;
+        }
+      }
+      "banner" => self.banner = Some(Banner::new(value)?),
+      "reveal" => self.reveal = Some(Reveal::new(value)?),
+      "seed" => self.seed = Some(value.parse()?),
+      _ => anyhow::bail!("unknown option"),
+    }
+
+    Ok(())
+  }
+
+  /// Every setting as the `key`/`value` pair [`Config::set`] accepts, so applying
+  /// them in order reproduces this config. A glyph source assigned directly to
+  /// [`Config::glyphs`] rather than through `set` can't be described and is left out.
+  pub fn entries(&self) -> Vec<(&'static str, String)> {
+    let color = |color: Color| match color.to_rgb() {
+      Some((r, g, b)) => format!("{:02X}{:02X}{:02X}", r, g, b),
+      None => "reset".to_owned(),
+    };
+
+    let mut entries = vec![
+      ("dense", self.dense.to_string()),
+      ("drops", self.drops_count.to_string()),
+      ("min-length", self.drop_length_range.start.to_string()),
+      ("max-length", self.drop_length_range.end.to_string()),
+      (
+        "length-dist",
+        match self.length_distribution {
+          LengthDistribution::Uniform => "uniform".to_owned(),
+          LengthDistribution::Normal { mean, stddev } => format!("normal:{}:{}", mean, stddev),
+          LengthDistribution::Exponential { lambda } => format!("exp:{}", lambda),
+        },
+      ),
+      (
+        "style",
+        match self.style {
+          RainStyle::Rainbow => "rainbow".to_owned(),
+          RainStyle::Solid(c) => format!("solid:{}", color(c)),
+          RainStyle::Shader(_) => {
+            let name = self.shader_setting.as_deref().unwrap_or("custom");
+            format!("shader:{}", name)
+          }
+        },
+      ),
+      ("delay", format_duration(self.frame_delay)),
+      ("head-length", self.head_length.to_string()),
+      ("head-bold", self.head_bold.to_string()),
+      ("head-color", color(self.head_color)),
+    ];
+    if let Some(period) = self.hue_cycle {
+      entries.push(("hue-cycle", format_duration(period)));
+    }
+    entries.extend([
+      ("gravity", self.gravity.to_string()),
+      ("max-speed", self.max_speed.to_string()),
+      ("shockwave", self.shockwave.to_string()),
+      (
+        "recycle",
+        match self.recycle {
+          RecyclePolicy::NewColumn => "new",
+          RecyclePolicy::SameColumn => "same",
+          RecyclePolicy::Off => "off",
+        }
+        .to_owned(),
+      ),
+    ]);
+    if let Some(margin) = self.recycle_margin {
+      entries.push(("recycle-margin", margin.to_string()));
+    }
+    entries.extend([
+      (
+        "max-spawns-per-frame",
+        self.max_spawns_per_frame.to_string(),
+      ),
+      ("column-cooldown", self.column_cooldown.to_string()),
+      ("column-layers", self.column_layers.to_string()),
+      ("fill", self.fill.to_string()),
+      ("start-delay", self.max_start_delay.to_string()),
+    ]);
+    if let Some(rows) = self.spawn_above {
+      entries.push(("spawn-above", rows.to_string()));
+    }
+    if !self.excluded_columns.is_empty() {
+      let ranges: Vec<_> = self
+        .excluded_columns
+        .iter()
+        .map(|gutter| format!("{}:{}", gutter.start, gutter.end))
+        .collect();
+      entries.push(("exclude", ranges.join(",")));
+    }
+    if let Some((key, value)) = &self.glyphs_setting {
+      entries.push((key, value.clone()));
+    }
+    entries.extend([
+      ("stable-glyphs", self.stable_glyphs.to_string()),
+      ("text-share", self.text_share.to_string()),
+      ("motion-blur", self.motion_blur.to_string()),
+      ("glitch-fraction", self.glitch_fraction.to_string()),
+      ("trail-density", self.trail_density.to_string()),
+      ("tail-dither", self.tail_dither.to_string()),
+      (
+        "fade-curve",
+        match self.fade_curve {
+          FadeCurve::Linear => "linear",
+          FadeCurve::Quadratic => "quadratic",
+          FadeCurve::Exponential => "exp",
+        }
+        .to_owned(),
+      ),
+      (
+        "unfocused-brightness",
+        self.unfocused_brightness.to_string(),
+      ),
+      ("unfocused-fps", self.unfocused_fps.to_string()),
+      ("canvas", self.canvas.to_string()),
+      ("canvas-decay", self.canvas_decay.to_string()),
+    ]);
+    if let Some(accent) = self.accent_color {
+      entries.push(("accent", color(accent)));
+    }
+    if let Some(erase) = self.erase_color {
+      entries.push(("erase-color", color(erase)));
+    }
+    entries.push(("background", color(self.background)));
+    entries.push(("accent-rate", self.accent_rate.to_string()));
+    entries.push(("flicker", self.flicker_rate.to_string()));
+    if let Some(impact) = self.impact {
+      entries.push(("impact", impact.to_string()));
+    }
+    entries.extend([
+      ("vignette", self.vignette.to_string()),
+      ("scanlines", self.scanlines.to_string()),
+      ("tail-alpha", self.tail_alpha.to_string()),
+      ("temperature", self.temperature.to_string()),
+    ]);
+    if let Some((x, y)) = self.background_text_position {
+      entries.push(("bg-pos", format!("{}:{}", x, y)));
+    }
+    if let Some(banner) = &self.banner {
+      entries.push(("banner", banner.text().to_owned()));
+    }
+    if let Some(reveal) = &self.reveal {
+      entries.push(("reveal", reveal.text()));
+    }
+    if let Some(seed) = self.seed {
+      entries.push(("seed", seed.to_string()));
+    }
+
+    entries
+  }
+
+  /// A short streak of bright cells that drops off sharply into a faint tail.
+  /// The colors are kept, so it combines with any color style set before it.
+  fn apply_comet_preset(&mut self) {
+    self.head_length = 3;
+    self.fade_curve = FadeCurve::Exponential;
+    self.drop_length_range = 8..16;
+  }
+
+  /// A churning wall of glyphs: every column streams continuously with short
+  /// gaps, quickly, and all glyphs shimmer. Settings given after it still apply.
+  fn apply_dense_preset(&mut self) {
+    self.recycle = RecyclePolicy::SameColumn;
+    self.max_start_delay = 3;
+    self.drop_length_range = 10..30;
+    self.frame_delay = Duration::from_millis(50);
+    self.glitch_fraction = 1.0;
+  }
+
+  pub fn validate(&self) -> anyhow::Result<()> {
+    // a zero delay is written as `0ms`, which doesn't read back, and stalls everything
+    // counted in frame delays
+    if self.frame_delay.is_zero() {
+      anyhow::bail!("`delay` must be longer than zero");
+    }
+    let Range { start, end } = self.drop_length_range;
+    if start == 0 {
+      anyhow::bail!("`min-length` must be at least 1");
+    }
+    if start > end {
+      anyhow::bail!("`min-length` must not exceed `max-length`");
+    }
+    match self.length_distribution {
+      LengthDistribution::Uniform => {}
+      LengthDistribution::Normal { mean, stddev } => {
+        if !mean.is_finite() || !stddev.is_finite() || stddev <= 0.0 {
+          anyhow::bail!("`length-dist` needs a finite mean and a standard deviation above zero");
+        }
+      }
+      LengthDistribution::Exponential { lambda } => {
+        if !lambda.is_finite() || lambda <= 0.0 {
+          anyhow::bail!("`length-dist` needs a finite rate above zero");
+        }
+      }
+    }
+    if !(1..=MAX_COLUMN_LAYERS).contains(&self.column_layers) {
+      anyhow::bail!("`column-layers` must be within 1..{}", MAX_COLUMN_LAYERS);
+    }
+    if self.hue_cycle.is_some_and(|period| period.is_zero()) {
+      anyhow::bail!("`hue-cycle` must be longer than zero");
+    }
+    if let Some(gutter) = self
+      .excluded_columns
+      .iter()
+      .find(|gutter| gutter.is_empty())
+    {
+      anyhow::bail!(
+        "excluded columns {}:{} are empty, the end is exclusive",
+        gutter.start,
+        gutter.end
+      );
+    }
+    if self.head_length > start {
+      anyhow::bail!("`head-length` must not exceed `min-length`");
+    }
+    if self.max_spawns_per_frame == 0 {
+      anyhow::bail!("`max-spawns-per-frame` must be at least 1");
+    }
+    // an infinite speed turns a drop's progress into NaN, which then never advances:
+    // every drop would freeze on the spot
+    if !(self.gravity.is_finite() && self.gravity >= 0.0) {
+      anyhow::bail!("`gravity` must be a finite number, not negative");
+    }
+    if !(self.shockwave.is_finite() && self.shockwave >= 0.0) {
+      anyhow::bail!("`shockwave` must be a finite number, not negative");
+    }
+    if !(self.max_speed.is_finite() && self.max_speed > 0.0) {
+      anyhow::bail!("`max-speed` must be a finite number greater than zero");
+    }
+    if !(0.0..=1.0).contains(&self.text_share) {
+      anyhow::bail!("`text-share` must be within 0..1");
+    }
+    if !(0.0..=1.0).contains(&self.glitch_fraction) {
+      anyhow::bail!("`glitch-fraction` must be within 0..1");
+    }
+    if !(0.0..=1.0).contains(&self.trail_density) {
+      anyhow::bail!("`trail-density` must be within 0..1");
+    }
+    if !(0.0..=1.0).contains(&self.unfocused_brightness) {
+      anyhow::bail!("`unfocused-brightness` must be within 0..1");
+    }
+    if !(self.unfocused_fps > 0.0 && self.unfocused_fps <= 1.0) {
+      anyhow::bail!("`unfocused-fps` must be within (0, 1]");
+    }
+    if !(0.0..1.0).contains(&self.canvas_decay) {
+      anyhow::bail!("`canvas-decay` must be within 0..1");
+    }
+    if !(0.0..=1.0).contains(&self.accent_rate) {
+      anyhow::bail!("`accent-rate` must be within 0..1");
+    }
+    if !(0.0..=1.0).contains(&self.flicker_rate) {
+      anyhow::bail!("`flicker` must be within 0..1");
+    }
+    if !(0.0..=1.0).contains(&self.vignette) {
+      anyhow::bail!("`vignette` must be within 0..1");
+    }
+    if !(0.0..=1.0).contains(&self.tail_alpha) {
+      anyhow::bail!("`tail-alpha` must be within 0..1");
+    }
+    if !(-1.0..=1.0).contains(&self.temperature) {
+      anyhow::bail!("`temperature` must be within -1..1");
+    }
+
+    Ok(())
+  }
+}
+
+/// Parses `250ms`, `0.1s`, `20s` or `5m`, the single duration syntax of every setting.
+/// Zero and negative durations are rejected.
+pub fn parse_duration(s: &str) -> anyhow::Result<Duration> {
+  const EXPECTED: &str = "expected a duration like `250ms`, `0.1s`, `20s` or `5m`";
+
+  let split = s
+    .find(|c: char| !c.is_ascii_digit() && c != '.')
+    .unwrap_or(s.len());
+  let (number, unit) = s.split_at(split);
+  let Ok(number) = number.parse::<f64>() else {
+    anyhow::bail!(EXPECTED);
+  };
+  let seconds = match unit {
+    "ms" => number / 1000.0,
+    "s" => number,
+    "m" => number * 60.0,
+    _ => anyhow::bail!(EXPECTED),
+  };
+
+  let Ok(duration) = Duration::try_from_secs_f64(seconds) else {
+    anyhow::bail!(EXPECTED);
+  };
+  if duration.is_zero() {
+    anyhow::bail!("the duration must be longer than zero");
+  }
+  Ok(duration)
+}
+
+/// Formats `duration` so [`parse_duration`] reads it back, in whole milliseconds when
+/// it has no finer part.
+pub fn format_duration(duration: Duration) -> String {
+  if duration.subsec_nanos().is_multiple_of(1_000_000) {
+    format!("{}ms", duration.as_millis())
+  } else {
+    format!("{}s", duration.as_secs_f64())
+  }
+}
+
+/// Parses `matrix`, `message:<text>` or `weighted:<glyph>=<weight>,...`.
+fn parse_glyphs(s: &str) -> anyhow::Result<Arc<dyn GlyphSource>> {
+  match s {
+    "matrix" => return Ok(Arc::new(Authentic::default())),
+    "words" => return Ok(Arc::new(Words::default())),
+    "connectors" => return Ok(Arc::new(Connectors)),
+    _ => {}
+  }
+
+  match s.split_once(':') {
+    Some(("message", text)) => Ok(Arc::new(Message::new(text)?)),
+    Some(("weighted", weights)) => Ok(Arc::new(Weighted::parse(weights)?)),
+    _ => anyhow::bail!(
+      "expected `matrix`, `words`, `connectors`, `message:<text>` or `weighted:<glyph>=<weight>,...`"
+    ),
+  }
+}
+
+/// Parses comma separated `start:end` column ranges, the end is exclusive.
+fn parse_columns(s: &str) -> anyhow::Result<Vec<Range<u16>>> {
+  s.split(',')
+    .map(|range| {
+      let (start, end) = range
+        .split_once(':')
+        .context("expected `start:end`, e.g. `30:50`")?;
+      Ok(start.trim().parse()?..end.trim().parse()?)
+    })
+    .collect()
+}
+
+fn parse_length_distribution(s: &str) -> anyhow::Result<LengthDistribution> {
+  let mut parts = s.split(':');
+  let distribution = match (parts.next(), parts.next(), parts.next(), parts.next()) {
+    (Some("uniform"), None, None, None) => LengthDistribution::Uniform,
+    (Some("normal"), Some(mean), Some(stddev), None) => LengthDistribution::Normal {
+      mean: mean.parse()?,
+      stddev: stddev.parse()?,
+    },
+    (Some("exp"), Some(lambda), None, None) => LengthDistribution::Exponential {
+      lambda: lambda.parse()?,
+    },
+    _ => anyhow::bail!("expected `uniform`, `normal:<mean>:<stddev>` or `exp:<rate>`"),
+  };
+
+  Ok(distribution)
+}
+
+fn parse_recycle(s: &str) -> anyhow::Result<RecyclePolicy> {
+  match s {
+    "new" => Ok(RecyclePolicy::NewColumn),
+    "same" => Ok(RecyclePolicy::SameColumn),
+    "off" => Ok(RecyclePolicy::Off),
+    _ => anyhow::bail!("expected `new`, `same` or `off`"),
+  }
+}
+
+fn parse_fade_curve(s: &str) -> anyhow::Result<FadeCurve> {
+  match s {
+    "linear" => Ok(FadeCurve::Linear),
+    "quadratic" => Ok(FadeCurve::Quadratic),
+    "exp" => Ok(FadeCurve::Exponential),
+    _ => anyhow::bail!("expected `linear`, `quadratic` or `exp`"),
+  }
+}
+
+fn parse_style(s: &str) -> anyhow::Result<RainStyle> {
+  match s.split_once(':') {
+    None if s == "rainbow" => Ok(RainStyle::Rainbow),
+    Some(("solid", color)) => Ok(RainStyle::Solid(Color::parse(color)?)),
+    Some(("shader", name)) => shader::builtin(name)
+      .map(RainStyle::Shader)
+      .with_context(|| format!("unknown shader `{}`, expected `plasma` or `radial`", name)),
+    _ => anyhow::bail!("expected `rainbow`, `solid:<color>` or `shader:<name>`"),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn zero_delay_is_rejected() {
+    let mut config = Config::default();
+    config.set("delay-ms", "0").unwrap();
+    assert!(config.validate().is_err());
+    assert!(config.set("delay", "0ms").is_err());
+  }
+
+  #[test]
+  fn printed_settings_read_back() {
+    let mut config = Config::default();
+    config.set("delay-ms", "1").unwrap();
+
+    let mut read = Config::default();
+    for (key, value) in config.entries() {
+      read
+        .set(key, &value)
+        .unwrap_or_else(|e| panic!("`{} = {}` doesn't read back: {}", key, value, e));
+    }
+    read.validate().unwrap();
+    assert_eq!(read.entries(), config.entries());
+  }
+
+  #[test]
+  fn durations_round_trip() {
+    for s in ["1ms", "250ms", "0.1s", "20s", "5m"] {
+      let duration = parse_duration(s).unwrap();
+      assert_eq!(
+        parse_duration(&format_duration(duration)).unwrap(),
+        duration
+      );
+    }
+    assert!(parse_duration("0s").is_err());
+    assert!(parse_duration("-1s").is_err());
+  }
+
+  #[test]
+  fn speeds_that_would_freeze_drops_are_rejected() {
+    for (key, value) in [
+      ("max-speed", "0"),
+      ("max-speed", "-1"),
+      ("max-speed", "inf"),
+      ("max-speed", "NaN"),
+      ("gravity", "inf"),
+      ("gravity", "NaN"),
+      ("gravity", "-0.1"),
+    ] {
+      let mut config = Config::default();
+      config.set(key, value).unwrap();
+      assert!(
+        config.validate().is_err(),
+        "`{} = {}` is accepted",
+        key,
+        value
+      );
+    }
+
+    // no gravity is a uniform speed, not a frozen one
+    let mut config = Config::default();
+    config.set("gravity", "0").unwrap();
+    config.validate().unwrap();
+  }
+}
@@ -0,0 +1,156 @@
+use crate::render;
+use crossterm::cursor::MoveTo;
+use crossterm::queue;
+use crossterm::style::{Color, Print, ResetColor};
+use rand::rngs::SmallRng;
+use rand::SeedableRng;
+use rmatrix::glyph::Glyph;
+use rmatrix::{Frame, Rain};
+use std::io::Write;
+use std::time::Duration;
+
+const FONT_WIDTH: u16 = 5;
+const FONT_HEIGHT: u16 = 7;
+
+/// 5x7 bitmaps of `0`-`9` and `:`, one row per byte with the leftmost pixel in bit 4.
+const FONT: [[u8; FONT_HEIGHT as usize]; 11] = [
+  [0x0E, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0E],
+  [0x04, 0x0C, 0x04, 0x04, 0x04, 0x04, 0x0E],
+  [0x0E, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1F],
+  [0x1F, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0E],
+  [0x02, 0x06, 0x0A, 0x12, 0x1F, 0x02, 0x02],
+  [0x1F, 0x10, 0x1E, 0x01, 0x01, 0x11, 0x0E],
+  [0x06, 0x08, 0x10, 0x1E, 0x11, 0x11, 0x0E],
+  [0x1F, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08],
+  [0x0E, 0x11, 0x11, 0x0E, 0x11, 0x11, 0x0E],
+  [0x0E, 0x11, 0x11, 0x0F, 0x01, 0x02, 0x0C],
+  [0x00, 0x0C, 0x0C, 0x00, 0x0C, 0x0C, 0x00],
+];
+
+fn bitmap(c: char) -> &'static [u8; FONT_HEIGHT as usize] {
+  match c {
+    ':' => &FONT[10],
+    c => &FONT[c.to_digit(10).expect("only digits and `:` are drawn") as usize],
+  }
+}
+
+/// Large centered countdown drawn in rain glyphs on top of the rain.
+///
+/// Time is counted in frame delays rather than read from the clock, so a replay
+/// reaches zero on the same frame as the recording.
+pub struct Countdown {
+  remaining: Duration,
+  /// Cells drawn last frame, erased before the next one.
+  shown: Vec<(u16, u16)>,
+}
+
+impl Countdown {
+  pub fn new(duration: Duration) -> Self {
+    Self {
+      remaining: duration,
+      shown: Vec::new(),
+    }
+  }
+
+  /// Counts down one frame that lasted `frame_delay`, returns `true` once zero is reached.
+  pub fn advance(&mut self, frame_delay: Duration) -> bool {
+    self.remaining = self.remaining.saturating_sub(frame_delay);
+    self.remaining.is_zero()
+  }
+
+  /// Whole seconds left, rounded up so `0` only shows once time is up.
+  fn text(&self) -> String {
+    let secs = self.remaining.as_secs() + (self.remaining.subsec_nanos() > 0) as u64;
+    if secs >= 60 {
+      format!("{}:{:02}", secs / 60, secs % 60)
+    } else {
+      secs.to_string()
+    }
+  }
+
+  /// Blanks what the last [`Countdown::draw`] left, to be called before the rain is rendered
+  /// so the rain can redraw its own cells underneath.
+  pub fn erase(&mut self, rain: &Rain, out: &mut impl Write) -> anyhow::Result<()> {
+    let (width, height) = rain.size();
+    let blank = " ".repeat(rain.config().glyphs.width() as usize);
+
+    for (x, y) in self.shown.drain(..) {
+      if x >= width || y >= height {
+        continue;
+      }
+
+      queue!(out, MoveTo(x, y))?;
+      if let Some(bg) = rain.background(x, y) {
+        queue!(out, render::set_bg(bg))?;
+      }
+      queue!(out, Print(&blank))?;
+    }
+
+    Ok(())
+  }
+
+  /// Draws the remaining time centered on the screen, clipped when it doesn't fit.
+  pub fn draw(&mut self, rain: &Rain, out: &mut impl Write) -> anyhow::Result<()> {
+    for (x, y, glyph) in self.cells(rain) {
+      queue!(out, MoveTo(x, y))?;
+      if let Some(bg) = rain.background(x, y) {
+        queue!(out, render::set_bg(bg))?;
+      }
+      queue!(out, render::set_fg(Color::White), Print(glyph))?;
+      self.shown.push((x, y));
+    }
+
+    if rain.has_background() {
+      queue!(out, ResetColor)?;
+    }
+
+    Ok(())
+  }
+
+  /// Like [`Countdown::draw`], onto a frame about to be emitted instead of the screen.
+  pub fn paint(&self, rain: &Rain, frame: &mut Frame) {
+    for (x, y, glyph) in self.cells(rain) {
+      if let Some(cell) = frame.get_mut(x, y) {
+        cell.glyph = glyph;
+        cell.fg = Color::White;
+        cell.bold = false;
+      }
+    }
+  }
+
+  /// Cells of the remaining time, centered on the screen and clipped to it.
+  fn cells(&self, rain: &Rain) -> Vec<(u16, u16, Glyph)> {
+    let (width, height) = rain.size();
+    let glyphs = rain.config().glyphs.as_ref();
+    let cell_width = glyphs.width() as u16;
+
+    let text = self.text();
+    let pixels_wide = text.chars().count() as u16 * (FONT_WIDTH + 1) - 1;
+    let left = width.saturating_sub(pixels_wide * cell_width) / 2;
+    let top = height.saturating_sub(FONT_HEIGHT) / 2;
+    // new glyphs every second, stable in between
+    let salt = self.remaining.as_secs();
+    let mut cells = Vec::new();
+
+    for (i, c) in text.chars().enumerate() {
+      for (row, bits) in bitmap(c).iter().enumerate() {
+        for col in 0..FONT_WIDTH {
+          if bits & (1 << (FONT_WIDTH - 1 - col)) == 0 {
+            continue;
+          }
+
+          let x = left + (i as u16 * (FONT_WIDTH + 1) + col) * cell_width;
+          let y = top + row as u16;
+          if x + cell_width > width || y >= height {
+            continue;
+          }
+
+          let mut rng = SmallRng::seed_from_u64(salt ^ ((x as u64) << 32 | y as u64));
+          cells.push((x, y, glyphs.glyph(x, y, 0, &mut rng)));
+        }
+      }
+    }
+
+    cells
+  }
+}
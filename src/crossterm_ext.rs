@@ -0,0 +1,291 @@
+use crossterm::style::Color;
+
+/// Every named color [`ColorExt::parse`] accepts, in the order of the standard palette.
+pub const NAMED_COLORS: [(&str, Color); 16] = [
+  ("black", Color::Black),
+  ("dark_red", Color::DarkRed),
+  ("dark_green", Color::DarkGreen),
+  ("dark_yellow", Color::DarkYellow),
+  ("dark_blue", Color::DarkBlue),
+  ("dark_magenta", Color::DarkMagenta),
+  ("dark_cyan", Color::DarkCyan),
+  ("grey", Color::Grey),
+  ("dark_grey", Color::DarkGrey),
+  ("red", Color::Red),
+  ("green", Color::Green),
+  ("yellow", Color::Yellow),
+  ("blue", Color::Blue),
+  ("magenta", Color::Magenta),
+  ("cyan", Color::Cyan),
+  ("white", Color::White),
+];
+
+pub trait ColorExt {
+  fn rgb(r: u8, g: u8, b: u8) -> Color;
+
+  /// Like [`ColorExt::rgb`], but for channels computed in wider arithmetic: each one
+  /// saturates to `0..=255` instead of wrapping.
+  fn rgb_clamped(r: i16, g: i16, b: i16) -> Color;
+
+  /// Parses a named color (`green`, `dark_red`, ...), a hex `RRGGBB` value or `reset`,
+  /// the terminal's default color.
+  fn parse(s: &str) -> anyhow::Result<Color>;
+
+  /// Scales the brightness by `factor` (`0.0..=1.0`), named colors other than white are kept as is.
+  fn dimmed(self, factor: f32) -> Color;
+
+  /// Rotates the hue by `degrees`, keeping saturation and value.
+  /// `Reset` is returned unchanged.
+  fn rotate_hue(self, degrees: f32) -> Color;
+
+  /// Shifts the color warmer (toward red, `temperature > 0`) or cooler (toward blue,
+  /// `temperature < 0`), `temperature` is within `-1.0..=1.0`. `Reset` is returned unchanged.
+  fn tempered(self, temperature: f32) -> Color;
+
+  /// Mixes in `amount` (`0.0..=1.0`) of `other`, unchanged if either has no known RGB value.
+  fn blend(self, other: Color, amount: f32) -> Color;
+
+  /// RGB channels of the color, named colors use the common xterm palette.
+  /// `None` for `Reset` whose actual value depends on the terminal.
+  fn to_rgb(self) -> Option<(u8, u8, u8)>;
+
+  /// Nearest color of the xterm 256-color palette, from its 6x6x6 cube or its grey
+  /// ramp. Named and ANSI colors and `Reset` are returned unchanged.
+  fn to_ansi256(self) -> Color;
+
+  /// Nearest of the 16 [`NAMED_COLORS`], `Reset` is returned unchanged.
+  fn to_ansi16(self) -> Color;
+}
+
+impl ColorExt for Color {
+  fn rgb(r: u8, g: u8, b: u8) -> Color {
+    Color::Rgb { r, g, b }
+  }
+
+  fn rgb_clamped(r: i16, g: i16, b: i16) -> Color {
+    let clamp = |c: i16| c.clamp(0, 255) as u8;
+    Color::rgb(clamp(r), clamp(g), clamp(b))
+  }
+
+  fn parse(s: &str) -> anyhow::Result<Color> {
+    if s == "reset" {
+      return Ok(Color::Reset);
+    }
+    if let Ok(color) = Color::try_from(s) {
+      return Ok(color);
+    }
+
+    let hex = s.strip_prefix('#').unwrap_or(s);
+    if hex.len() != 6 || !hex.is_ascii() {
+      anyhow::bail!("unknown color `{}`", s);
+    }
+
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16);
+    Ok(Color::rgb(channel(0)?, channel(2)?, channel(4)?))
+  }
+
+  fn dimmed(self, factor: f32) -> Color {
+    let scale = |c: u8| (c as f32 * factor) as i16;
+
+    match self {
+      _ if factor >= 1.0 => self,
+      Color::Rgb { r, g, b } => Color::rgb_clamped(scale(r), scale(g), scale(b)),
+      Color::White => Color::rgb_clamped(scale(255), scale(255), scale(255)),
+      color => color,
+    }
+  }
+
+  fn rotate_hue(self, degrees: f32) -> Color {
+    let Some((r, g, b)) = self.to_rgb() else {
+      return self;
+    };
+
+    let (h, s, v) = rgb_to_hsv(r, g, b);
+    let (r, g, b) = hsv_to_rgb((h + degrees).rem_euclid(360.0), s, v);
+    Color::rgb(r, g, b)
+  }
+
+  fn tempered(self, temperature: f32) -> Color {
+    let Some((r, g, b)) = self.to_rgb().filter(|_| temperature != 0.0) else {
+      return self;
+    };
+
+    let t = temperature.clamp(-1.0, 1.0);
+    // only ever scale channels down, so the result stays in range
+    let (kr, kg, kb) = if t > 0.0 {
+      (1.0, 1.0 - 0.3 * t, 1.0 - 0.8 * t)
+    } else {
+      (1.0 + 0.8 * t, 1.0 + 0.3 * t, 1.0)
+    };
+    let scale = |c: u8, k: f32| (c as f32 * k) as i16;
+    Color::rgb_clamped(scale(r, kr), scale(g, kg), scale(b, kb))
+  }
+
+  fn blend(self, other: Color, amount: f32) -> Color {
+    let (Some((r1, g1, b1)), Some((r2, g2, b2))) = (self.to_rgb(), other.to_rgb()) else {
+      return self;
+    };
+
+    let amount = amount.clamp(0.0, 1.0);
+    let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * amount) as i16;
+    Color::rgb_clamped(mix(r1, r2), mix(g1, g2), mix(b1, b2))
+  }
+
+  fn to_rgb(self) -> Option<(u8, u8, u8)> {
+    const SYSTEM: [(u8, u8, u8); 16] = [
+      (0, 0, 0),
+      (128, 0, 0),
+      (0, 128, 0),
+      (128, 128, 0),
+      (0, 0, 128),
+      (128, 0, 128),
+      (0, 128, 128),
+      (192, 192, 192),
+      (128, 128, 128),
+      (255, 0, 0),
+      (0, 255, 0),
+      (255, 255, 0),
+      (0, 0, 255),
+      (255, 0, 255),
+      (0, 255, 255),
+      (255, 255, 255),
+    ];
+
+    Some(match self {
+      Color::Reset => return None,
+      Color::Rgb { r, g, b } => (r, g, b),
+      Color::Black => SYSTEM[0],
+      Color::DarkRed => SYSTEM[1],
+      Color::DarkGreen => SYSTEM[2],
+      Color::DarkYellow => SYSTEM[3],
+      Color::DarkBlue => SYSTEM[4],
+      Color::DarkMagenta => SYSTEM[5],
+      Color::DarkCyan => SYSTEM[6],
+      Color::Grey => SYSTEM[7],
+      Color::DarkGrey => SYSTEM[8],
+      Color::Red => SYSTEM[9],
+      Color::Green => SYSTEM[10],
+      Color::Yellow => SYSTEM[11],
+      Color::Blue => SYSTEM[12],
+      Color::Magenta => SYSTEM[13],
+      Color::Cyan => SYSTEM[14],
+      Color::White => SYSTEM[15],
+      Color::AnsiValue(i @ 0..=15) => SYSTEM[i as usize],
+      Color::AnsiValue(i @ 16..=231) => {
+        let level = |v: u8| if v == 0 { 0 } else { 55 + v * 40 };
+        let i = i - 16;
+        (level(i / 36), level(i / 6 % 6), level(i % 6))
+      }
+      Color::AnsiValue(i) => {
+        let v = 8 + (i - 232) * 10;
+        (v, v, v)
+      }
+    })
+  }
+
+  fn to_ansi256(self) -> Color {
+    let Color::Rgb { r, g, b } = self else {
+      return self;
+    };
+
+    // nearest of the cube levels 0, 95, 135, 175, 215 and 255
+    let step = |c: u8| match c {
+      0..=47 => 0,
+      48..=114 => 1,
+      _ => (c - 35) / 40,
+    };
+    let cube = 16 + 36 * step(r) + 6 * step(g) + step(b);
+    let average = (r as u16 + g as u16 + b as u16) / 3;
+    let grey = 232 + (average.saturating_sub(3) / 10).min(23) as u8;
+
+    [cube, grey]
+      .into_iter()
+      .map(Color::AnsiValue)
+      .min_by_key(|color| distance((r, g, b), named_color_to_rgb(*color)))
+      .unwrap_or(self)
+  }
+
+  fn to_ansi16(self) -> Color {
+    let Some(rgb) = self.to_rgb() else {
+      return self;
+    };
+
+    NAMED_COLORS
+      .iter()
+      .map(|&(_, color)| color)
+      .min_by_key(|color| distance(rgb, named_color_to_rgb(*color)))
+      .unwrap_or(self)
+  }
+}
+
+/// Squared euclidean distance between two RGB colors.
+fn distance((r1, g1, b1): (u8, u8, u8), (r2, g2, b2): (u8, u8, u8)) -> u32 {
+  let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2) as u32;
+  d(r1, r2) + d(g1, g2) + d(b1, b2)
+}
+
+/// Concrete RGB of any color, named and ANSI colors are looked up in the standard palette.
+/// [`Color::Reset`] has no value of its own and becomes the usual default foreground, light grey.
+pub fn named_color_to_rgb(color: Color) -> (u8, u8, u8) {
+  color.to_rgb().unwrap_or((192, 192, 192))
+}
+
+/// Hue in degrees `0..360`, saturation and value in `0..=1`.
+pub fn rgb_to_hsv(r: u8, g: u8, b: u8) -> (f32, f32, f32) {
+  let [r, g, b] = [r, g, b].map(|c| c as f32 / 255.0);
+  let max = r.max(g).max(b);
+  let delta = max - r.min(g).min(b);
+
+  let h = if delta == 0.0 {
+    0.0
+  } else if max == r {
+    60.0 * ((g - b) / delta).rem_euclid(6.0)
+  } else if max == g {
+    60.0 * ((b - r) / delta + 2.0)
+  } else {
+    60.0 * ((r - g) / delta + 4.0)
+  };
+  let s = if max == 0.0 { 0.0 } else { delta / max };
+
+  (h, s, max)
+}
+
+pub fn hsv_to_rgb(h: f32, s: f32, v: f32) -> (u8, u8, u8) {
+  let c = v * s;
+  let x = c * (1.0 - ((h / 60.0).rem_euclid(2.0) - 1.0).abs());
+  let m = v - c;
+
+  let (r, g, b) = match h as u32 {
+    0..=59 => (c, x, 0.0),
+    60..=119 => (x, c, 0.0),
+    120..=179 => (0.0, c, x),
+    180..=239 => (0.0, x, c),
+    240..=299 => (x, 0.0, c),
+    _ => (c, 0.0, x),
+  };
+  let channel = |c: f32| ((c + m) * 255.0).round() as u8;
+
+  (channel(r), channel(g), channel(b))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn rgb_clamped_saturates_every_channel() {
+    assert_eq!(Color::rgb_clamped(0, 128, 255), Color::rgb(0, 128, 255));
+    assert_eq!(Color::rgb_clamped(-1, 256, 300), Color::rgb(0, 255, 255));
+    assert_eq!(
+      Color::rgb_clamped(i16::MIN, i16::MAX, -255),
+      Color::rgb(0, 255, 0)
+    );
+  }
+
+  #[test]
+  fn dimming_stays_within_range() {
+    assert_eq!(Color::rgb(200, 100, 0).dimmed(0.5), Color::rgb(100, 50, 0));
+    assert_eq!(Color::rgb(200, 100, 10).dimmed(0.0), Color::rgb(0, 0, 0));
+    assert_eq!(Color::White.dimmed(1.0), Color::White);
+  }
+}
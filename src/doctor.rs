@@ -0,0 +1,202 @@
+use crate::cli::Options;
+use crate::graphics::{GraphicsMode, Protocol};
+use crate::render::ColorMode;
+use crate::terminal::RenderTarget;
+use crossterm::queue;
+use crossterm::style::Print;
+use std::io::{stderr, stdout, IsTerminal, Write};
+
+/// Something about the terminal that doesn't suit the options, and what to do about it.
+struct Problem {
+  what: String,
+  fix: String,
+}
+
+/// Prints what rmatrix can tell about the terminal from the environment, and the
+/// problems the resolved `options` would run into on it, each with a way out.
+///
+/// Nothing is drawn and the terminal isn't queried: capabilities are detected the
+/// same way a run detects them, so this shows what a run would assume.
+pub fn print(out: &mut impl Write, options: &Options) -> anyhow::Result<()> {
+  let var = |name| std::env::var(name).unwrap_or_default();
+  let (term, colorterm) = (var("TERM"), var("COLORTERM"));
+  let mut problems = Vec::new();
+
+  let is_terminal = match options.render_to {
+    RenderTarget::Stdout => stdout().is_terminal(),
+    RenderTarget::Stderr => stderr().is_terminal(),
+  };
+  let size = crossterm::terminal::window_size().ok();
+  let colors = ColorMode::detect();
+  let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+    .into_iter()
+    .map(var)
+    .find(|value| !value.is_empty())
+    .unwrap_or_default();
+  let utf8 = locale.to_lowercase().replace('-', "").contains("utf8");
+  let protocol = GraphicsMode::Auto.protocol();
+  let tmux = std::env::var_os("TMUX").is_some();
+
+  let mut lines = vec![
+    (
+      "output",
+      format!(
+        "{}, {}",
+        options.render_to,
+        if is_terminal {
+          "a terminal"
+        } else {
+          "not a terminal"
+        }
+      ),
+    ),
+    (
+      "size",
+      match &size {
+        Some(size) if size.width > 0 && size.height > 0 => format!(
+          "{}x{} cells, {}x{} pixels",
+          size.columns, size.rows, size.width, size.height
+        ),
+        Some(size) => format!("{}x{} cells, pixels unknown", size.columns, size.rows),
+        None => "unknown".to_owned(),
+      },
+    ),
+    ("TERM", format!("{:?}", term)),
+    ("COLORTERM", format!("{:?}", colorterm)),
+    ("colors", colors.to_string()),
+    ("locale", format!("{:?}", locale)),
+    (
+      "graphics",
+      format!(
+        "{}, {} the `graphics` feature",
+        match protocol {
+          Some(Protocol::Kitty) => "kitty",
+          Some(Protocol::Sixel) => "sixel",
+          None => "none detected",
+        },
+        if cfg!(feature = "graphics") {
+          "built with"
+        } else {
+          "built without"
+        }
+      ),
+    ),
+  ];
+  if tmux {
+    lines.push(("multiplexer", "tmux".to_owned()));
+  }
+
+  if !is_terminal {
+    problems.push(Problem {
+      what: format!("{} isn't a terminal", options.render_to),
+      fix: "run in a terminal, or draw on the other stream with `--render-to`".to_owned(),
+    });
+  }
+  if term == "dumb" || term.is_empty() {
+    problems.push(Problem {
+      what: format!(
+        "$TERM is {:?}, cursor movement and colors may not work",
+        term
+      ),
+      fix: "run in a terminal emulator that sets $TERM, e.g. xterm-256color".to_owned(),
+    });
+  } else if options.alt_screen && term == "linux" {
+    problems.push(Problem {
+      what: "the Linux console has no alternate screen, the rain stays after exit".to_owned(),
+      fix: "pass `--no-alt-screen` to draw on the main screen knowingly".to_owned(),
+    });
+  }
+
+  match (options.color_mode, colors) {
+    (ColorMode::Truecolor, ColorMode::Ansi256 | ColorMode::Ansi16 | ColorMode::Mono) => problems
+      .push(Problem {
+        what: format!(
+          "`--color-mode truecolor`, but the terminal looks like {} colors",
+          colors
+        ),
+        fix: "set COLORTERM=truecolor if it shows 24-bit colors, else drop `--color-mode`"
+          .to_owned(),
+      }),
+    (ColorMode::Auto, ColorMode::Ansi256 | ColorMode::Ansi16) => problems.push(Problem {
+      what: format!("only {} colors were detected, fades are coarser", colors),
+      fix: "set COLORTERM=truecolor if the terminal shows 24-bit colors".to_owned(),
+    }),
+    _ => {}
+  }
+
+  let glyphs = options.config.glyphs.glyphs();
+  if !utf8 && glyphs.iter().any(|glyph| !glyph.to_string().is_ascii()) {
+    problems.push(Problem {
+      what: format!(
+        "the locale {:?} isn't UTF-8, the glyphs may show as garbage",
+        locale
+      ),
+      fix:
+        "set LANG to a UTF-8 locale, e.g. LANG=C.UTF-8, or use ASCII glyphs, e.g. `--charset ascii`"
+          .to_owned(),
+    });
+  }
+  let width = options.config.glyphs.width() as u16;
+  if size.as_ref().is_some_and(|size| size.columns < width) {
+    problems.push(Problem {
+      what: format!(
+        "the glyphs are {} columns wide, wider than the terminal",
+        width
+      ),
+      fix: "widen the terminal or pick narrower glyphs with `--charset`".to_owned(),
+    });
+  }
+
+  if options.graphics != GraphicsMode::Off {
+    if options.graphics.protocol().is_none() {
+      problems.push(Problem {
+        what: "`--graphics auto` detects no graphics protocol, the glow is left out".to_owned(),
+        fix: "pass `--graphics kitty` or `--graphics sixel` if the terminal speaks one".to_owned(),
+      });
+    } else if size
+      .as_ref()
+      .is_none_or(|size| size.width == 0 || size.height == 0)
+    {
+      problems.push(Problem {
+        what: "the terminal doesn't report its size in pixels, the glow is left out".to_owned(),
+        fix: "use a terminal that does, e.g. kitty, WezTerm, foot or xterm".to_owned(),
+      });
+    }
+    if tmux {
+      problems.push(Problem {
+        what: "tmux doesn't pass pictures through by default".to_owned(),
+        fix: "run outside tmux, or `set -g allow-passthrough on` (tmux 3.3+)".to_owned(),
+      });
+    }
+  }
+  let focus_dependent =
+    options.config.unfocused_brightness < 1.0 || options.config.unfocused_fps < 1.0;
+  if tmux && focus_dependent {
+    problems.push(Problem {
+      what:
+        "dimming and slowing down while unfocused needs focus events, which tmux drops by default"
+          .to_owned(),
+      fix: "`set -g focus-events on` in tmux.conf".to_owned(),
+    });
+  }
+
+  let label = lines.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+  for (name, value) in &lines {
+    queue!(out, Print(format!("{:<label$}  {}\n", name, value)))?;
+  }
+  queue!(out, Print("\n"))?;
+  if problems.is_empty() {
+    queue!(out, Print("No problems found with these options.\n"))?;
+  }
+  for problem in &problems {
+    queue!(
+      out,
+      Print(format!(
+        "warning: {}\n  try: {}\n",
+        problem.what, problem.fix
+      ))
+    )?;
+  }
+  out.flush()?;
+  Ok(())
+}
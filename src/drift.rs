@@ -0,0 +1,34 @@
+use std::time::Duration;
+
+/// Offsets the picture steps through, one cell apart, around a square of [`MARGIN`] cells.
+const PATH: [(u16, u16); 4] = [(0, 0), (1, 0), (1, 1), (0, 1)];
+
+/// Cells the rain is narrower and shorter than the terminal, the room it drifts in.
+pub const MARGIN: u16 = 1;
+
+/// Burn-in protection: the whole picture moves by a cell every period, so no cell of
+/// an always-on display lights up the same way for hours.
+///
+/// The rain runs [`MARGIN`] cells smaller than the terminal and is placed at the
+/// current offset, nothing is ever pushed off screen. Time is counted in frame delays
+/// like the countdown, so a replay drifts on the same frames as the recording.
+pub struct Drift {
+  period: Duration,
+}
+
+impl Drift {
+  pub fn new(period: Duration) -> Self {
+    Self { period }
+  }
+
+  /// Size of the rain on a `width` x `height` terminal.
+  pub fn inner(width: u16, height: u16) -> (u16, u16) {
+    (width.saturating_sub(MARGIN), height.saturating_sub(MARGIN))
+  }
+
+  /// Position of the rain's top left corner `elapsed` into the run.
+  pub fn offset(&self, elapsed: Duration) -> (u16, u16) {
+    let step = elapsed.as_nanos() / self.period.as_nanos();
+    PATH[(step % PATH.len() as u128) as usize]
+  }
+}
@@ -0,0 +1,737 @@
+use crate::crossterm_ext::{named_color_to_rgb, ColorExt};
+use crate::glyph::Glyph;
+use crate::shader::Shader;
+use crate::source::GlyphSource;
+use crossterm::style::Color;
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
+use std::ops::Range;
+
+/// Frame-wide settings the drops need to build their parts.
+pub struct DrawContext<'a> {
+  pub glyphs: &'a dyn GlyphSource,
+  /// Random glyphs of the drops that don't spell `glyphs`, see [`RainDrop::filler`].
+  pub filler: &'a dyn GlyphSource,
+  /// Number of bright cells at the front of every drop.
+  pub head_length: u8,
+  /// Color of the head cells.
+  pub head_color: Color,
+  /// Degrees the hue of every drop is currently rotated by.
+  pub hue_shift: f32,
+  /// Current frame, glitchy drops reshuffle their glyphs based on it.
+  pub frame: u64,
+  /// Glyphs belong to the drop and scroll down with it, instead of belonging to
+  /// the screen row so the drop seems to slide over fixed glyphs.
+  pub stable_glyphs: bool,
+  /// Brightness and fade length of every drop follow its speed.
+  pub motion_blur: bool,
+  /// Share of tail cells that show a glyph, the others are drawn blank.
+  pub trail_density: f32,
+  /// Most cells at the tip of a tail that randomly blank out, each drop picks its own depth.
+  pub tail_dither: u8,
+  pub fade_curve: FadeCurve,
+  /// Colors drop cells instead of the drops' own colors.
+  pub shader: Option<ShaderContext<'a>>,
+}
+
+/// A [`Shader`] with the screen size and time it is evaluated for this frame.
+pub struct ShaderContext<'a> {
+  pub shader: &'a Shader,
+  pub width: u16,
+  pub height: u16,
+  /// Simulated seconds since the start.
+  pub seconds: f32,
+}
+
+/// How the brightness of a trail falls off from the head toward the tail.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FadeCurve {
+  /// Evenly from the head down to black at the tail.
+  Linear,
+  /// Dims quicker right behind the head.
+  Quadratic,
+  /// Only the cells near the head keep much of their color.
+  Exponential,
+}
+
+/// Steepness of [`FadeCurve::Exponential`].
+const EXP_STEEPNESS: f32 = 4.0;
+
+impl FadeCurve {
+  /// Brightness at `t`, from 0 at the tail to 1 at the head. Every curve rises
+  /// monotonically from 0 to 1.
+  pub fn level(self, t: f32) -> f32 {
+    match self {
+      FadeCurve::Linear => t,
+      FadeCurve::Quadratic => t * t,
+      FadeCurve::Exponential => (EXP_STEEPNESS * t).exp_m1() / EXP_STEEPNESS.exp_m1(),
+    }
+  }
+}
+
+/// One in this many cells of a glitchy drop swaps its glyph each frame.
+const GLITCH_ODDS: u64 = 4;
+
+/// Keeps the tail dither rolls apart from the other per-drop rolls.
+const DITHER_SALT: u64 = 0xD1_7E;
+
+/// Cheap integer mixer (splitmix64 finalizer) for deterministic per-cell randomness.
+pub(crate) fn mix(mut z: u64) -> u64 {
+  z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+  z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+  z ^ (z >> 31)
+}
+
+/// The color `r, g, b` at `level` of its brightness.
+fn scaled(r: u8, g: u8, b: u8, level: f32) -> Color {
+  let scale = |c: u8| (c as f32 * level) as i16;
+  Color::rgb_clamped(scale(r), scale(g), scale(b))
+}
+
+fn shift_hue((r, g, b): (u8, u8, u8), degrees: f32) -> (u8, u8, u8) {
+  if degrees == 0.0 {
+    (r, g, b)
+  } else {
+    named_color_to_rgb(Color::rgb(r, g, b).rotate_hue(degrees))
+  }
+}
+
+pub struct RainDropPart(pub Glyph, pub Color);
+
+pub struct RainDrop {
+  length: u8,
+  /// Normalized to RGB on construction so every drop takes the same brightness ramp.
+  /// `None` for [`Color::Reset`], the terminal's default foreground, which has no
+  /// known RGB value and is used unramped for the whole tail.
+  color: Option<(u8, u8, u8)>,
+  /// Rows per frame, only fractional while accelerating under gravity.
+  speed: f32,
+  /// Fraction of a row fallen but not yet shown.
+  progress: f32,
+  /// Rows the top of the drawn trail moved down by the last fall, see [`RainDrop::vacated_rows`].
+  vacated: u16,
+  /// Rows the last fall jumped over between the old head and the new top. They are
+  /// drawn above the tail until the next fall, so a fast drop leaves no gap.
+  streak: u16,
+  y: u16,
+  x: u16,
+  /// Frames left before the drop starts falling and becomes visible.
+  dormant: u8,
+  /// Rows the head still has to fall to reach the top row, it is at `-above`
+  /// while `y` stays 0. See [`RainDrop::spawned_above`].
+  above: u16,
+  /// Per-drop salt for glyph selection, drawn from the seeded rng so runs are reproducible.
+  seed: usize,
+  /// Glitchy drops shimmer, some of their glyphs change every frame.
+  glitchy: bool,
+  /// Depth within the column, 0 in front, see [`RainDrop::layered`].
+  layer: u8,
+  /// Shows random glyphs instead of the configured source's, see [`RainDrop::filler`].
+  filler: bool,
+}
+
+impl RainDrop {
+  /// Parts from the tail (index 0) to the head (last index).
+  pub fn get_parts(&self, ctx: &DrawContext) -> Box<[RainDropPart]> {
+    let mut res: Vec<RainDropPart> = Vec::with_capacity(self.length as usize + 1);
+    let color = self.shifted_color(ctx.hue_shift);
+    let top = self.y as i32 - self.length as i32;
+    let color_of = |i: u8| match &ctx.shader {
+      Some(s) => {
+        let y = (top + i as i32).max(0) as u16;
+        let shaded = (s.shader)(self.x, y, s.width, s.height, s.seconds);
+        Some(shift_hue(named_color_to_rgb(shaded), ctx.hue_shift))
+      }
+      None => color,
+    };
+
+    // brightness ramp from black at the tail up to the drop's color
+    let length = self.length as i16;
+    let step = |c: u8, i: i16| c as i16 / length * i;
+
+    for i in 0..self.length {
+      let j = i as i16;
+      let level = ctx.fade_curve.level(i as f32 / self.length as f32);
+      // the terminal's default foreground has no known brightness to ramp
+      let Some((r, g, b)) = color_of(i) else {
+        res.push(RainDropPart(self.tail_glyph(i, ctx), Color::Reset));
+        continue;
+      };
+      let color = if ctx.motion_blur {
+        self.blurred(r, g, b, level)
+      } else if ctx.fade_curve == FadeCurve::Linear {
+        Color::rgb_clamped(step(r, j), step(g, j), step(b, j))
+      } else {
+        scaled(r, g, b, level)
+      };
+      res.push(RainDropPart(self.tail_glyph(i, ctx), color));
+    }
+
+    let head = color_of(self.length).map_or(Color::Reset, |(r, g, b)| Color::rgb(r, g, b));
+    res.push(RainDropPart(self.get_char_for_part(res.len(), ctx), head));
+
+    let bright = res.len().saturating_sub(ctx.head_length as usize);
+    for part in &mut res[bright..] {
+      part.1 = ctx.head_color;
+    }
+
+    res.into_boxed_slice()
+  }
+
+  /// Tail color at fade `level` under motion blur. At one row per frame the ramp falls off
+  /// quickly behind the head, every extra row of speed dims the drop and stretches the fade.
+  fn blurred(&self, r: u8, g: u8, b: u8, level: f32) -> Color {
+    let speed = self.speed.max(1.0);
+    scaled(
+      r,
+      g,
+      b,
+      level.powf(2.0 / speed) / (1.0 + (speed - 1.0) * 0.25),
+    )
+  }
+
+  /// Parts that are on screen, paired with the row they occupy and their index from the tail.
+  pub fn visible_parts(
+    &self,
+    ctx: &DrawContext,
+    buffer_h: u16,
+  ) -> impl Iterator<Item = (u16, usize, RainDropPart)> + '_ {
+    let parts = self.get_parts(ctx).into_vec();
+    let top = self.y as i32 - self.length as i32;
+    // the rows jumped over show in the faintest color of the tail
+    let mut streak = Vec::new();
+    if let Some(tail) = parts.first() {
+      for y in (top - self.streak as i32).max(0)..top.min(buffer_h as i32) {
+        let glyph = self.streak_glyph(y as u16, ctx);
+        streak.push((y as u16, 0, RainDropPart(glyph, tail.1)));
+      }
+    }
+
+    streak.into_iter().chain(
+      parts
+        .into_iter()
+        .enumerate()
+        .map(move |(i, part)| (top + i as i32, i, part))
+        .filter(move |(y, _, _)| (0..buffer_h as i32).contains(y))
+        .map(|(y, i, part)| (y as u16, i, part)),
+    )
+  }
+
+  /// Rows the tail vacated with the last fall: everything between the old and the new top.
+  /// Rows above the screen are skipped rather than clamped to row 0, where they would
+  /// erase whatever another drop has there.
+  pub fn vacated_rows(&self) -> impl Iterator<Item = u16> + '_ {
+    let top = self.y as i32 - self.length as i32 - self.streak as i32;
+    (1..=self.vacated as i32)
+      .map(move |i| top - i)
+      .filter(|&y| y >= 0)
+      .map(|y| y as u16)
+  }
+
+  /// The drop's color rotated by `hue_shift`, `None` for the terminal's default foreground.
+  pub fn shifted_color(&self, hue_shift: f32) -> Option<(u8, u8, u8)> {
+    self.color.map(|color| shift_hue(color, hue_shift))
+  }
+
+  /// Glyph of tail part `i`, blank if it is a hole or dithered out.
+  fn tail_glyph(&self, i: u8, ctx: &DrawContext) -> Glyph {
+    if self.is_hole(i as usize, ctx.trail_density) || self.is_dithered(i, ctx.tail_dither) {
+      Glyph::new(&" ".repeat(ctx.glyphs.width() as usize))
+    } else {
+      self.get_char_for_part(i as usize, ctx)
+    }
+  }
+
+  pub fn length(&self) -> u8 {
+    self.length
+  }
+
+  pub fn x(&self) -> u16 {
+    self.x
+  }
+
+  pub fn layer(&self) -> u8 {
+    self.layer
+  }
+
+  /// Whether the drop hasn't reached the screen yet: it is still waiting for its
+  /// start delay or falling toward the top row from above.
+  pub fn is_dormant(&self) -> bool {
+    self.dormant > 0 || self.above > 0
+  }
+
+  /// Screen cell of the bright head, if it is visible.
+  pub fn head(&self, buffer_h: u16) -> Option<(u16, u16)> {
+    (!self.is_dormant() && self.y < buffer_h).then_some((self.x, self.y))
+  }
+
+  /// Whether the whole drop, tail and streak included, has left a screen `buffer_h`
+  /// rows high. Heads reach the last row like any other: the drop is only recycled
+  /// once its top is past the bottom, so every row it fell through has been drawn.
+  pub fn is_end(&self, buffer_h: u16) -> bool {
+    self.top() > buffer_h
+  }
+
+  /// Whether the head is `margin` rows or more below a screen `buffer_h` rows high,
+  /// however much of the tail is still on it.
+  pub fn is_past(&self, buffer_h: u16, margin: u16) -> bool {
+    self.y >= buffer_h.saturating_add(margin)
+  }
+
+  /// Rows of a screen `buffer_h` rows high the drop covers, from the top of its streak
+  /// to its head.
+  pub fn covered_rows(&self, buffer_h: u16) -> Range<u16> {
+    self.top().min(buffer_h)..self.y.saturating_add(1).min(buffer_h)
+  }
+
+  /// Row of the topmost drawn cell, 0 while that is above the screen.
+  fn top(&self) -> u16 {
+    self
+      .y
+      .saturating_sub(self.length as u16)
+      .saturating_sub(self.streak)
+  }
+
+  /// Moves the drop down, speeding it up by `gravity` per frame up to `max_speed`.
+  /// `boost` multiplies the speed of this single fall.
+  ///
+  /// The speed is always positive: drops start at 1 or 2 rows per frame, or at
+  /// `gravity` under gravity, and [`Config::validate`](crate::Config::validate) rejects
+  /// a zero `max_speed` and infinite values, so there is no frozen rain.
+  ///
+  /// However far a fall goes, the rows between the old head and the new top are drawn
+  /// as the drop's streak until the next one, so a fast drop is a continuous trail
+  /// rather than dashes with undrawn rows in between.
+  pub fn fall(&mut self, gravity: f32, max_speed: f32, boost: f32) {
+    if gravity > 0.0 {
+      self.speed = (self.speed + gravity).min(max_speed);
+    }
+
+    self.progress += self.speed * boost / (self.layer + 1) as f32;
+    // the cast saturates, an absurd speed only takes the drop far below the screen
+    let step = self.progress as u16;
+    self.progress = self.progress.fract();
+    let streak = step.saturating_sub(self.length as u16 + 1);
+    // the old streak is left along with the rows the top moved by
+    self.vacated = step.saturating_add(self.streak) - streak;
+    self.streak = streak;
+    // rows above the screen are used up first
+    let entered = step.saturating_sub(self.above);
+    self.above -= step - entered;
+    self.y = self.y.saturating_add(entered);
+  }
+
+  /// Moves the head to row `y` and lets the drop fall right away.
+  pub fn place(&mut self, y: u16) {
+    self.y = y;
+    self.dormant = 0;
+    self.above = 0;
+  }
+
+  /// Starts the head `rows` rows above the top row instead of on screen, so it
+  /// scrolls in over as many rows of falling.
+  pub fn spawned_above(mut self, rows: u16) -> Self {
+    self.y = 0;
+    self.above = rows;
+    self
+  }
+
+  /// Counts down the start delay, returns `true` while the drop is still dormant.
+  pub fn wait(&mut self) -> bool {
+    if self.dormant == 0 {
+      return false;
+    }
+
+    self.dormant -= 1;
+    true
+  }
+
+  /// Whether tail part `i` is left blank. Holes are keyed on the drop and the part,
+  /// so they travel down with the drop and the same seed gives the same pattern.
+  fn is_hole(&self, i: usize, density: f32) -> bool {
+    if density >= 1.0 {
+      return false;
+    }
+
+    let roll = mix(self.seed as u64 ^ (i as u64) << 32) as f64 / u64::MAX as f64;
+    roll >= density as f64
+  }
+
+  /// Whether tail part `i` is blanked at the dissolving tip of the tail. The drop dithers
+  /// its last `1..=max` cells, the odds of a blank rising toward the very end. Rolls are
+  /// keyed on the drop and the screen row, so the edge flickers as the drop falls but
+  /// the same seed gives the same pattern.
+  fn is_dithered(&self, i: u8, max: u8) -> bool {
+    if max == 0 {
+      return false;
+    }
+
+    let depth = (1 + mix(self.seed as u64) % max as u64).min(self.length as u64);
+    let i = i as u64;
+    if i >= depth {
+      return false;
+    }
+
+    let row = (self.y as i64 - self.length as i64 + i as i64) as u64;
+    let roll = mix(mix(self.seed as u64 ^ DITHER_SALT) ^ row) as f64 / u64::MAX as f64;
+    roll < (depth - i) as f64 / (depth + 1) as f64
+  }
+
+  /// Glyph of the streak on row `y`, see [`RainDrop::fall`].
+  fn streak_glyph(&self, y: u16, ctx: &DrawContext) -> Glyph {
+    let mut rng = SmallRng::seed_from_u64(mix(self.seed as u64 ^ mix(y as u64)));
+    let source = if self.filler { ctx.filler } else { ctx.glyphs };
+    source.drop_glyph(self.seed as u64, self.x, y, 0, &mut rng)
+  }
+
+  fn get_char_for_part(&self, i: usize, ctx: &DrawContext) -> Glyph {
+    let position = if ctx.stable_glyphs {
+      i
+    } else {
+      self.y as usize + i
+    };
+    let hash = self.seed.wrapping_mul(31) + position * 31;
+    let mut seed = hash as u64;
+
+    if self.glitchy {
+      let glitch = mix(hash as u64 ^ mix(ctx.frame));
+      if glitch.is_multiple_of(GLITCH_ODDS) {
+        seed = glitch;
+      }
+    }
+
+    let y = (self.y as usize + i).saturating_sub(self.length as usize) as u16;
+    let mut rng = SmallRng::seed_from_u64(seed);
+    let source = if self.filler { ctx.filler } else { ctx.glyphs };
+    source.drop_glyph(self.seed as u64, self.x, y, i, &mut rng)
+  }
+
+  pub fn new(
+    length: u8,
+    color: Color,
+    x: u16,
+    max_start_delay: u8,
+    glitchy: bool,
+    gravity: f32,
+    rng: &mut impl Rng,
+  ) -> Self {
+    let y = rng.gen_range(1..8);
+    let speed = rng.gen_range(1..3_u8) as f32;
+
+    Self {
+      length,
+      color: color.to_rgb(),
+      x,
+      y,
+      // under gravity drops start from (almost) standing still
+      speed: if gravity > 0.0 { gravity } else { speed },
+      progress: 0.0,
+      vacated: 0,
+      streak: 0,
+      dormant: rng.gen_range(0..=max_start_delay),
+      above: 0,
+      seed: rng.gen_range(0..u32::MAX as usize),
+      glitchy,
+      layer: 0,
+      filler: false,
+    }
+  }
+
+  /// Puts the drop `layer` layers behind the front of its column. Every layer back
+  /// halves, thirds, ... the distance it falls per frame.
+  pub fn layered(mut self, layer: u8) -> Self {
+    self.layer = layer;
+    self
+  }
+
+  /// Takes the glyphs from [`DrawContext::filler`] rather than the configured source,
+  /// for the drops around a message or words.
+  pub fn filler(mut self) -> Self {
+    self.filler = true;
+    self
+  }
+
+  /// Where drops overlap, the cells of the one with the higher priority show: drops
+  /// spelling the configured source rank above filler drops.
+  pub fn priority(&self) -> u8 {
+    !self.filler as u8
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::glyph::{get_all_unicode_chars, Charset};
+  use crate::source::Random;
+  use rand::SeedableRng;
+
+  /// A drop of `length` with its head on the top row of column 0, falling `speed`
+  /// rows per frame when it falls without gravity.
+  fn falling(length: u8, speed: f32) -> RainDrop {
+    let mut rng = SmallRng::seed_from_u64(7);
+    // under gravity the speed starts at the gravity, kept by falling without it
+    let mut drop = RainDrop::new(length, Color::Green, 0, 0, false, speed, &mut rng);
+    drop.place(0);
+    drop
+  }
+
+  fn drop(length: u8) -> RainDrop {
+    falling(length, 1.0)
+  }
+
+  /// Plain drawing with `glyphs` and a one cell head.
+  fn context(glyphs: &Random, fade_curve: FadeCurve) -> DrawContext<'_> {
+    DrawContext {
+      glyphs,
+      filler: glyphs,
+      head_length: 1,
+      head_color: Color::White,
+      hue_shift: 0.0,
+      frame: 0,
+      stable_glyphs: false,
+      motion_blur: false,
+      trail_density: 1.0,
+      tail_dither: 0,
+      fade_curve,
+      shader: None,
+    }
+  }
+
+  #[test]
+  fn head_reaches_the_last_row_before_the_drop_ends() {
+    let height = 10;
+    let mut drop = drop(3);
+    let mut heads = Vec::new();
+    while !drop.is_end(height) {
+      heads.extend(drop.head(height).map(|(_, y)| y));
+      drop.fall(0.0, 1.0, 1.0);
+    }
+
+    assert_eq!(heads, (0..height).collect::<Vec<_>>());
+    assert!(drop.covered_rows(height).is_empty());
+  }
+
+  #[test]
+  fn vacated_rows_are_exactly_the_rows_left() {
+    let height = 12;
+    for speed in [0.5, 1.0, 2.0, 3.0] {
+      for length in 1..6 {
+        let mut drop = falling(length, speed);
+        while !drop.is_end(height) {
+          let before = drop.covered_rows(height);
+          drop.fall(0.0, speed, 1.0);
+          let after = drop.covered_rows(height);
+
+          let left: Vec<u16> = before.filter(|y| !after.contains(y)).collect();
+          let mut vacated: Vec<u16> = drop.vacated_rows().filter(|&y| y < height).collect();
+          vacated.sort_unstable();
+          assert_eq!(vacated, left, "speed {}, length {}", speed, length);
+        }
+      }
+    }
+  }
+
+  #[test]
+  fn a_drop_entering_at_the_top_vacates_nothing() {
+    let mut drop = falling(5, 2.0);
+    for _ in 0..2 {
+      drop.fall(0.0, 2.0, 1.0);
+      // its top is still above the screen, row 0 may hold another drop's head
+      assert_eq!(drop.vacated_rows().count(), 0);
+    }
+  }
+
+  #[test]
+  fn fast_drops_leave_no_gaps() {
+    let glyphs = Random::new(Charset::from_chars(get_all_unicode_chars()));
+    let ctx = context(&glyphs, FadeCurve::Linear);
+    let height = 200;
+    for speed in [5.0, 20.0, 150.0, 1000.0] {
+      for length in [1, 3, 8] {
+        let mut drop = falling(length, speed);
+        let mut drawn = vec![false; height as usize];
+        let mut frames = 0;
+        while !drop.is_end(height) {
+          frames += 1;
+          let rows: Vec<u16> = drop
+            .visible_parts(&ctx, height)
+            .map(|(y, _, _)| y)
+            .collect();
+          // one unbroken run from the top of the streak to the head
+          assert_eq!(
+            rows,
+            drop.covered_rows(height).collect::<Vec<_>>(),
+            "speed {}, length {}",
+            speed,
+            length
+          );
+          rows.iter().for_each(|&y| drawn[y as usize] = true);
+
+          let head = drop.covered_rows(height).end;
+          drop.fall(0.0, speed, 1.0);
+          // the new trail starts right below the old head, if not higher
+          let top = drop.covered_rows(height).start;
+          assert!(top <= head, "speed {}, length {}", speed, length);
+        }
+        assert!(
+          drawn.iter().all(|&row| row),
+          "speed {}, length {}",
+          speed,
+          length
+        );
+        // at its full speed, not slowed down to keep the trail whole
+        let bound = (height + length as u16) as f32 / speed + 2.0;
+        assert!(frames as f32 <= bound, "speed {}, length {}", speed, length);
+      }
+    }
+  }
+
+  #[test]
+  fn fade_curves_rise_from_tail_to_head() {
+    let curves = [
+      FadeCurve::Linear,
+      FadeCurve::Quadratic,
+      FadeCurve::Exponential,
+    ];
+    for curve in curves {
+      assert_eq!(curve.level(0.0), 0.0, "{:?}", curve);
+      assert!((curve.level(1.0) - 1.0).abs() < 1e-6, "{:?}", curve);
+      let levels: Vec<f32> = (0..=20).map(|i| curve.level(i as f32 / 20.0)).collect();
+      assert!(
+        levels.windows(2).all(|w| w[0] < w[1]),
+        "{:?}: {:?}",
+        curve,
+        levels
+      );
+    }
+    // both curves fall below linear, and the exponential one drops off sharpest
+    // right behind the head
+    for i in 1..20 {
+      let t = i as f32 / 20.0;
+      assert!(FadeCurve::Quadratic.level(t) < FadeCurve::Linear.level(t));
+      assert!(FadeCurve::Exponential.level(t) < FadeCurve::Linear.level(t));
+      if t >= 0.5 {
+        assert!(FadeCurve::Exponential.level(t) < FadeCurve::Quadratic.level(t));
+      }
+    }
+  }
+
+  #[test]
+  fn tails_brighten_toward_the_head() {
+    let glyphs = Random::new(Charset::from_chars(get_all_unicode_chars()));
+    for curve in [
+      FadeCurve::Linear,
+      FadeCurve::Quadratic,
+      FadeCurve::Exponential,
+    ] {
+      let mut drop = RainDrop::new(
+        10,
+        Color::rgb(0, 255, 0),
+        0,
+        0,
+        false,
+        0.0,
+        &mut SmallRng::seed_from_u64(1),
+      );
+      drop.place(12);
+      let parts = drop.get_parts(&context(&glyphs, curve));
+      let greens: Vec<u8> = parts[..parts.len() - 1]
+        .iter()
+        .map(|part| match part.1 {
+          Color::Rgb { g, .. } => g,
+          color => panic!("{:?}: tail part in {:?}", curve, color),
+        })
+        .collect();
+      assert!(
+        greens.windows(2).all(|w| w[0] <= w[1]),
+        "{:?}: {:?}",
+        curve,
+        greens
+      );
+      assert!(greens[0] < greens[9], "{:?}: {:?}", curve, greens);
+      assert_eq!(parts[10].1, Color::White);
+    }
+  }
+
+  #[test]
+  fn reset_drops_keep_the_default_foreground() {
+    let glyphs = Random::new(Charset::from_chars(get_all_unicode_chars()));
+    for curve in [FadeCurve::Linear, FadeCurve::Exponential] {
+      let mut drop = RainDrop::new(
+        6,
+        Color::Reset,
+        0,
+        0,
+        false,
+        0.0,
+        &mut SmallRng::seed_from_u64(2),
+      );
+      drop.place(10);
+      let parts = drop.get_parts(&context(&glyphs, curve));
+
+      assert_eq!(parts.len(), 7);
+      assert!(parts[..6].iter().all(|part| part.1 == Color::Reset));
+      assert_eq!(parts[6].1, Color::White);
+      assert_eq!(drop.shifted_color(90.0), None);
+    }
+  }
+
+  #[test]
+  fn a_margin_finishes_a_drop_once_its_head_is_past_it() {
+    let (height, margin) = (10, 2);
+    let mut drop = drop(8);
+    let mut frames = 0;
+    while !drop.is_past(height, margin) {
+      drop.fall(0.0, 1.0, 1.0);
+      frames += 1;
+    }
+
+    // right when the head is `margin` rows below, with most of the tail still on screen
+    assert_eq!(frames, height + margin);
+    assert_eq!(drop.covered_rows(height), 4..10);
+    assert!(!drop.is_end(height));
+    // without the margin it would take the whole length longer
+    while !drop.is_end(height) {
+      drop.fall(0.0, 1.0, 1.0);
+      frames += 1;
+    }
+    assert_eq!(frames, height + 8 + 1);
+  }
+
+  #[test]
+  fn heads_spawned_above_enter_at_the_top_row() {
+    for (rows, speed) in [(0, 1.0), (1, 1.0), (7, 1.0), (6, 2.0), (9, 0.5)] {
+      let mut drop = falling(4, speed).spawned_above(rows);
+      let mut frames = 0;
+      while drop.head(20).is_none() {
+        assert!(drop.is_dormant());
+        drop.fall(0.0, speed, 1.0);
+        frames += 1;
+      }
+
+      // a frame per `speed` rows above, landing on the top row as they divide evenly
+      assert_eq!(
+        frames,
+        (rows as f32 / speed).ceil() as u16,
+        "{} rows at {}",
+        rows,
+        speed
+      );
+      assert_eq!(drop.head(20), Some((0, 0)));
+    }
+  }
+
+  #[test]
+  fn start_delays_stay_within_the_configured_maximum() {
+    let mut rng = SmallRng::seed_from_u64(3);
+    for max_start_delay in [0, 1, 5, 30, u8::MAX] {
+      let delays: Vec<u8> = (0..500)
+        .map(|_| RainDrop::new(4, Color::Green, 0, max_start_delay, false, 0.0, &mut rng).dormant)
+        .collect();
+      assert!(delays.iter().all(|&delay| delay <= max_start_delay));
+      // spread over the whole range, not stuck at either end
+      assert!(delays.contains(&0));
+      assert!(delays.contains(&max_start_delay));
+    }
+  }
+}
@@ -0,0 +1,165 @@
+use crate::render;
+use crossterm::cursor::MoveTo;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use crossterm::queue;
+use crossterm::style::{Color, Print, ResetColor};
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
+use rmatrix::crossterm_ext::ColorExt;
+use rmatrix::{Frame, Rain};
+use std::io::Write;
+
+/// Keys that reveal a message, only when they aren't bound to an action.
+const MESSAGES: &[(char, &str)] = &[
+  ('n', "Neo"),
+  ('t', "Trinity"),
+  ('m', "Morpheus"),
+  ('w', "Wake up, Neo..."),
+  ('f', "Follow the white rabbit."),
+  ('k', "Knock, knock, Neo."),
+  ('s', "There is no spoon."),
+];
+
+/// Frames a revealed message stays fully visible once it is typed out.
+const HOLD: u32 = 20;
+
+/// Frames a message takes to fade away after the hold.
+const FADE: u32 = 10;
+
+/// A message being typed out, one char per frame, then held and faded.
+struct Reveal {
+  text: &'static str,
+  x: u16,
+  y: u16,
+  age: u32,
+}
+
+impl Reveal {
+  fn len(&self) -> u32 {
+    self.text.chars().count() as u32
+  }
+
+  fn is_over(&self) -> bool {
+    self.age >= self.len() + HOLD + FADE
+  }
+
+  /// Brightness of the message, 1 until the fade starts.
+  fn level(&self) -> f32 {
+    let fading = self.age.saturating_sub(self.len() + HOLD);
+    1.0 - fading as f32 / FADE as f32
+  }
+}
+
+/// Themed messages typed over the rain when certain keys are pressed.
+///
+/// Positions are picked from the rain's seed and frame, so a replay reveals
+/// its messages where the recording did.
+#[derive(Default)]
+pub struct EasterEggs {
+  reveals: Vec<Reveal>,
+  /// Cells drawn last frame, erased before the next one.
+  shown: Vec<(u16, u16)>,
+}
+
+impl EasterEggs {
+  /// Whether no message is being revealed or still on screen.
+  pub fn is_idle(&self) -> bool {
+    self.reveals.is_empty() && self.shown.is_empty()
+  }
+
+  /// Starts revealing the message of `key`, if it has one.
+  pub fn key(&mut self, key: &KeyEvent, rain: &Rain) {
+    if key.modifiers.contains(KeyModifiers::CONTROL) {
+      return;
+    }
+    let KeyCode::Char(c) = key.code else {
+      return;
+    };
+    let Some(&(_, text)) = MESSAGES.iter().find(|&&(k, _)| k == c) else {
+      return;
+    };
+
+    let (width, height) = rain.size();
+    let len = text.chars().count() as u16;
+    let mut rng = SmallRng::seed_from_u64(rain.seed() ^ rain.frame());
+    self.reveals.push(Reveal {
+      text,
+      x: rng.gen_range(0..=width.saturating_sub(len)),
+      y: rng.gen_range(0..height.max(1)),
+      age: 0,
+    });
+  }
+
+  /// Blanks what the last [`EasterEggs::draw`] left, to be called before the rain is rendered.
+  pub fn erase(&mut self, rain: &Rain, out: &mut impl Write) -> anyhow::Result<()> {
+    let (width, height) = rain.size();
+
+    for (x, y) in self.shown.drain(..) {
+      if x >= width || y >= height {
+        continue;
+      }
+
+      queue!(out, MoveTo(x, y))?;
+      if let Some(bg) = rain.background(x, y) {
+        queue!(out, render::set_bg(bg))?;
+      }
+      queue!(out, Print(' '))?;
+    }
+
+    Ok(())
+  }
+
+  /// Draws the running reveals on top of the rain and ages them by a frame.
+  pub fn draw(&mut self, rain: &Rain, out: &mut impl Write) -> anyhow::Result<()> {
+    for (x, y, c, color) in self.cells(rain) {
+      queue!(out, MoveTo(x, y))?;
+      if let Some(bg) = rain.background(x, y) {
+        queue!(out, render::set_bg(bg))?;
+      }
+      queue!(out, render::set_fg(color), Print(c))?;
+      self.shown.push((x, y));
+    }
+    self.age();
+
+    if rain.has_background() {
+      queue!(out, ResetColor)?;
+    }
+
+    Ok(())
+  }
+
+  /// Like [`EasterEggs::draw`], onto a frame about to be emitted instead of the screen.
+  pub fn paint(&mut self, rain: &Rain, frame: &mut Frame) {
+    for (x, y, c, color) in self.cells(rain) {
+      frame.put_str(x, y, c.encode_utf8(&mut [0; 4]), color);
+    }
+    self.age();
+  }
+
+  /// Typed chars of the running reveals with their color, clipped to the screen.
+  fn cells(&self, rain: &Rain) -> Vec<(u16, u16, char, Color)> {
+    let (width, height) = rain.size();
+    let mut cells = Vec::new();
+
+    for reveal in &self.reveals {
+      let color = Color::rgb(200, 255, 200).dimmed(reveal.level());
+      let typed = reveal.text.chars().take(reveal.age as usize + 1);
+
+      for (i, c) in typed.enumerate() {
+        let (x, y) = (reveal.x + i as u16, reveal.y);
+        if x < width && y < height {
+          cells.push((x, y, c, color));
+        }
+      }
+    }
+
+    cells
+  }
+
+  fn age(&mut self) {
+    for reveal in &mut self.reveals {
+      reveal.age += 1;
+    }
+    self.reveals.retain(|reveal| !reveal.is_over());
+  }
+}
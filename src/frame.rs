@@ -0,0 +1,203 @@
+use crate::glyph::Glyph;
+use crossterm::style::Color;
+use std::time::Duration;
+
+/// What a single screen cell of a [`Frame`] shows.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FrameCell {
+  pub glyph: Glyph,
+  pub fg: Color,
+  /// `None` keeps the terminal's background.
+  pub bg: Option<Color>,
+  /// Drawn in bold, see [`Config::head_bold`](crate::Config::head_bold).
+  pub bold: bool,
+}
+
+impl FrameCell {
+  /// An empty cell over `bg`.
+  pub fn blank(bg: Option<Color>) -> Self {
+    Self {
+      glyph: Glyph::from(' '),
+      fg: Color::Reset,
+      bg,
+      bold: false,
+    }
+  }
+}
+
+/// Complete picture of the screen for one frame, for hosts that would rather
+/// composite and emit whole frames than draw [`crate::Cell`]s as they come.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Frame {
+  width: u16,
+  height: u16,
+  cells: Vec<FrameCell>,
+}
+
+/// What an [`Overlay`] knows about the frame it draws on.
+#[derive(Clone, Copy, Debug)]
+pub struct FrameContext {
+  pub width: u16,
+  pub height: u16,
+  /// Index of the frame, counting ticks.
+  pub frame: u64,
+  /// Simulated time since the start, the sum of the frame delays so far.
+  pub elapsed: Duration,
+}
+
+/// Draws on top of every frame the rain composes, see [`crate::Rain::with_overlay`].
+pub type Overlay = Box<dyn Fn(&mut Frame, FrameContext) + Send>;
+
+impl Frame {
+  /// A blank frame.
+  pub fn new(width: u16, height: u16) -> Self {
+    Self {
+      width,
+      height,
+      cells: vec![FrameCell::blank(None); width as usize * height as usize],
+    }
+  }
+
+  pub fn width(&self) -> u16 {
+    self.width
+  }
+
+  pub fn height(&self) -> u16 {
+    self.height
+  }
+
+  fn index(&self, x: u16, y: u16) -> Option<usize> {
+    (x < self.width && y < self.height).then(|| y as usize * self.width as usize + x as usize)
+  }
+
+  pub fn get(&self, x: u16, y: u16) -> Option<&FrameCell> {
+    self.cells.get(self.index(x, y)?)
+  }
+
+  pub fn get_mut(&mut self, x: u16, y: u16) -> Option<&mut FrameCell> {
+    let i = self.index(x, y)?;
+    self.cells.get_mut(i)
+  }
+
+  /// Replaces the cell at `x, y`, out of bounds cells are ignored.
+  pub fn set(&mut self, x: u16, y: u16, cell: FrameCell) {
+    if let Some(slot) = self.get_mut(x, y) {
+      *slot = cell;
+    }
+  }
+
+  /// Writes `text` one char per cell starting at `x, y`, keeping the backgrounds
+  /// underneath. Clipped at the right edge.
+  pub fn put_str(&mut self, x: u16, y: u16, text: &str, fg: Color) {
+    for (i, c) in text.chars().enumerate() {
+      if let Some(cell) = self.get_mut(x.saturating_add(i as u16), y) {
+        cell.glyph = Glyph::from(c);
+        cell.fg = fg;
+        cell.bold = false;
+      }
+    }
+  }
+
+  /// A `width` x `height` frame with this one in its center, clipped evenly on
+  /// both sides along an axis where it is larger.
+  pub fn centered(&self, width: u16, height: u16) -> Frame {
+    let left = (width as i32 - self.width as i32) / 2;
+    let top = (height as i32 - self.height as i32) / 2;
+    self.placed(width, height, left, top)
+  }
+
+  /// A `width` x `height` frame with this one's top left corner at `left, top`,
+  /// which may be negative. Whatever falls outside is cropped, the rest is blank.
+  pub fn placed(&self, width: u16, height: u16, left: i32, top: i32) -> Frame {
+    let mut frame = Frame::new(width, height);
+
+    for (y, row) in self.rows().enumerate() {
+      for (x, cell) in row.iter().enumerate() {
+        let (x, y) = (left + x as i32, top + y as i32);
+        if let (Ok(x), Ok(y)) = (u16::try_from(x), u16::try_from(y)) {
+          frame.set(x, y, cell.clone());
+        }
+      }
+    }
+
+    frame
+  }
+
+  /// A `width` x `height` frame with this one in its top left corner, cropped or
+  /// padded with blank cells at the right and bottom.
+  pub fn resized(&self, width: u16, height: u16) -> Frame {
+    if (width, height) == (self.width, self.height) {
+      return self.clone();
+    }
+
+    self.placed(width, height, 0, 0)
+  }
+
+  /// Rows from top to bottom.
+  pub fn rows(&self) -> impl Iterator<Item = &[FrameCell]> {
+    self.cells.chunks(self.width.max(1) as usize)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// A frame of a running rain, as it would be on screen when pausing.
+  fn paused() -> Frame {
+    let mut config = crate::Config::default();
+    config.set("drops", "200").unwrap();
+    config.seed = Some(6);
+    let mut rain = crate::Rain::new(config, 24, 10);
+    for _ in 0..20 {
+      rain.tick();
+    }
+    rain.render_frame()
+  }
+
+  #[test]
+  fn a_resized_pause_shows_a_clipped_part_of_it() {
+    let frozen = paused();
+    let blank = FrameCell::blank(None);
+    assert!(frozen.cells.iter().filter(|cell| **cell != blank).count() > 20);
+
+    for (width, height) in [(10, 4), (24, 3), (0, 0), (30, 12)] {
+      let resized = frozen.resized(width, height);
+      for (x, y) in (0..width).flat_map(|x| (0..height).map(move |y| (x, y))) {
+        let cell = resized.get(x, y).unwrap();
+        assert_eq!(
+          cell,
+          frozen.get(x, y).unwrap_or(&blank),
+          "{},{} at {}x{}",
+          x,
+          y,
+          width,
+          height
+        );
+      }
+
+      // with a virtual size, the picture stays centered
+      let centered = frozen.centered(width, height);
+      let (dx, dy) = ((24 - width as i32) / 2, (10 - height as i32) / 2);
+      for (x, y) in (0..width).flat_map(|x| (0..height).map(move |y| (x, y))) {
+        let (fx, fy) = (x as i32 + dx, y as i32 + dy);
+        let cell = (fx >= 0 && fy >= 0)
+          .then(|| frozen.get(fx as u16, fy as u16))
+          .flatten()
+          .unwrap_or(&blank);
+        assert_eq!(
+          centered.get(x, y).unwrap(),
+          cell,
+          "{},{} at {}x{}",
+          x,
+          y,
+          width,
+          height
+        );
+      }
+    }
+
+    // every size is laid out from the retained frame, back at its own size it is whole
+    assert_eq!(frozen.resized(24, 10), frozen);
+  }
+}
@@ -0,0 +1,234 @@
+use std::fmt::{Display, Formatter};
+use std::sync::{Arc, OnceLock};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// A single user-perceived character (grapheme cluster) drawn in a rain cell.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Glyph {
+  text: Arc<str>,
+  width: u8,
+}
+
+impl Glyph {
+  pub fn new(text: &str) -> Self {
+    Self {
+      text: text.into(),
+      width: display_width(text),
+    }
+  }
+
+  /// Number of terminal columns the glyph occupies.
+  pub fn width(&self) -> u8 {
+    self.width
+  }
+}
+
+impl From<char> for Glyph {
+  fn from(c: char) -> Self {
+    Self::new(c.encode_utf8(&mut [0; 4]))
+  }
+}
+
+impl Display for Glyph {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    f.write_str(&self.text)
+  }
+}
+
+/// The printable ASCII characters, `!` through `~`, used when no charset is given.
+pub fn get_all_unicode_chars() -> &'static [char] {
+  static ALL_UNICODE_SYMBOLS: OnceLock<Vec<char>> = OnceLock::new();
+
+  ALL_UNICODE_SYMBOLS.get_or_init(|| {
+    // upper bound exclusive: 0x7F is DEL, a control character
+    (0x21..0x7F_u32)
+      .filter_map(std::char::from_u32)
+      .filter(|c| !c.is_whitespace() && !c.is_control())
+      .collect()
+  })
+}
+
+/// Set of glyphs the drops are built from.
+#[derive(Clone, Debug)]
+pub struct Charset {
+  glyphs: Box<[Glyph]>,
+  /// Widest glyph in the set, drops reserve this many columns.
+  width: u8,
+}
+
+impl Charset {
+  pub fn from_chars(chars: &[char]) -> Self {
+    Self::from_glyphs(chars.iter().copied().map(Glyph::from).collect())
+  }
+
+  /// Splits `s` into grapheme clusters, zero-width sequences are rejected since
+  /// they would draw nothing and break column placement.
+  pub fn parse(s: &str) -> anyhow::Result<Self> {
+    let mut glyphs = Vec::new();
+
+    for grapheme in s.graphemes(true) {
+      let glyph = Glyph::new(grapheme);
+      if glyph.width() == 0 {
+        anyhow::bail!("charset contains a zero-width sequence: {:?}", grapheme);
+      }
+      glyphs.push(glyph);
+    }
+
+    if glyphs.is_empty() {
+      anyhow::bail!("charset is empty");
+    }
+
+    Ok(Self::from_glyphs(glyphs))
+  }
+
+  fn from_glyphs(glyphs: Vec<Glyph>) -> Self {
+    let width = glyphs.iter().map(Glyph::width).max().unwrap_or(1);
+
+    Self {
+      glyphs: glyphs.into_boxed_slice(),
+      width,
+    }
+  }
+
+  /// One of the built-in sets: `katakana` (half-width), `digits`, `symbols`, `latin`
+  /// (letters) or `ascii` (all printable characters).
+  pub fn named(name: &str) -> Option<Self> {
+    let chars: Vec<char> = match name {
+      "katakana" => ('\u{FF66}'..='\u{FF9D}').collect(),
+      "digits" => ('0'..='9').collect(),
+      "symbols" => ":.\"=*+-<>¦|".chars().collect(),
+      "latin" => ('A'..='Z').chain('a'..='z').collect(),
+      "ascii" => get_all_unicode_chars().to_vec(),
+      _ => return None,
+    };
+
+    Some(Self::from_chars(&chars))
+  }
+
+  pub fn glyphs(&self) -> &[Glyph] {
+    &self.glyphs
+  }
+
+  /// Glyph at `i`, wrapping around the set.
+  pub fn get(&self, i: usize) -> &Glyph {
+    &self.glyphs[i % self.glyphs.len()]
+  }
+
+  pub fn len(&self) -> usize {
+    self.glyphs.len()
+  }
+
+  /// Always `false`, an empty set can't be built.
+  pub fn is_empty(&self) -> bool {
+    self.glyphs.is_empty()
+  }
+
+  pub fn width(&self) -> u8 {
+    self.width
+  }
+}
+
+/// Approximates the number of columns a grapheme cluster takes in a terminal.
+///
+/// Covers combining marks, zero-width joiners and variation selectors (0 columns),
+/// and the East Asian wide / emoji blocks (2 columns). Anything else is 1 column.
+pub fn display_width(grapheme: &str) -> u8 {
+  // VS16 requests emoji presentation which terminals render double-width
+  if grapheme.contains('\u{FE0F}') {
+    return 2;
+  }
+
+  grapheme.chars().map(char_width).max().unwrap_or(0)
+}
+
+fn char_width(c: char) -> u8 {
+  match c as u32 {
+    0x0300..=0x036F
+    | 0x0483..=0x0489
+    | 0x0591..=0x05BD
+    | 0x1AB0..=0x1AFF
+    | 0x1DC0..=0x1DFF
+    | 0x200B..=0x200F
+    | 0x2028..=0x202E
+    | 0x2060..=0x2064
+    | 0x20D0..=0x20FF
+    | 0xFE00..=0xFE0F
+    | 0xFE20..=0xFE2F
+    | 0xE0100..=0xE01EF => 0,
+    _ if c.is_control() => 0,
+    0x1100..=0x115F
+    | 0x231A..=0x231B
+    | 0x2329..=0x232A
+    | 0x23E9..=0x23EC
+    | 0x23F0
+    | 0x23F3
+    | 0x25FD..=0x25FE
+    | 0x2614..=0x2615
+    | 0x2648..=0x2653
+    | 0x267F
+    | 0x2693
+    | 0x26A1
+    | 0x26AA..=0x26AB
+    | 0x26BD..=0x26BE
+    | 0x26C4..=0x26C5
+    | 0x26CE
+    | 0x26D4
+    | 0x26EA
+    | 0x26F2..=0x26F5
+    | 0x26FA
+    | 0x26FD
+    | 0x2705
+    | 0x270A..=0x270B
+    | 0x2728
+    | 0x274C
+    | 0x2753..=0x2755
+    | 0x2757
+    | 0x2795..=0x2797
+    | 0x27B0
+    | 0x27BF
+    | 0x2B1B..=0x2B1C
+    | 0x2B50
+    | 0x2B55
+    | 0x2E80..=0x303E
+    | 0x3041..=0x33FF
+    | 0x3400..=0x4DBF
+    | 0x4E00..=0x9FFF
+    | 0xA000..=0xA4CF
+    | 0xAC00..=0xD7A3
+    | 0xF900..=0xFAFF
+    | 0xFE30..=0xFE4F
+    | 0xFF00..=0xFF60
+    | 0xFFE0..=0xFFE6
+    | 0x1F004
+    | 0x1F0CF
+    | 0x1F18E
+    | 0x1F191..=0x1F19A
+    | 0x1F200..=0x1F251
+    | 0x1F300..=0x1F64F
+    | 0x1F680..=0x1F6FF
+    | 0x1F7E0..=0x1F7EB
+    | 0x1F90C..=0x1F9FF
+    | 0x1FA70..=0x1FAFF
+    | 0x20000..=0x3FFFD => 2,
+    _ => 1,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn default_chars_are_printable() {
+    let chars = get_all_unicode_chars();
+    assert!(!chars.is_empty());
+    assert!(
+      chars.iter().all(|c| !c.is_control() && !c.is_whitespace()),
+      "{:?}",
+      chars
+    );
+    // every printable ASCII char but the space, `!` through `~`
+    assert_eq!(chars.len(), 94);
+    assert_eq!((chars[0], chars[93]), ('!', '~'));
+  }
+}
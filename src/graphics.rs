@@ -0,0 +1,118 @@
+//! Soft glow drawn under the rain as a picture, on terminals with a graphics protocol.
+//!
+//! The glyphs stay text: the frame grid is rasterized into a blurred image of the
+//! drops' colors, which is drawn beneath them. Needs the `graphics` build feature,
+//! `cargo build --features graphics`, and a terminal that reports its size in pixels.
+
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+#[cfg(feature = "graphics")]
+mod glow;
+
+#[cfg(feature = "graphics")]
+pub use glow::Glow;
+
+/// Whether and how the glow is drawn.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum GraphicsMode {
+  /// Only cells, no glow.
+  #[default]
+  Off,
+  /// A protocol picked from the environment, see [`GraphicsMode::protocol`].
+  Auto,
+  Kitty,
+  Sixel,
+}
+
+/// Escape sequences a picture is sent to the terminal with.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Protocol {
+  /// The kitty graphics protocol, also spoken by WezTerm and Ghostty. The picture is
+  /// placed beneath the text and scaled by the terminal.
+  Kitty,
+  /// DEC SIXEL, as in xterm (`-ti vt340`), foot, mlterm and contour. Text can't be
+  /// drawn over a picture, so the glyphs are printed again after it and cell
+  /// backgrounds, e.g. the canvas, are covered by the glow.
+  Sixel,
+}
+
+impl GraphicsMode {
+  /// The protocol the glow is drawn with, `None` for cells only.
+  ///
+  /// `auto` picks kitty in kitty (`$KITTY_WINDOW_ID`, `TERM=xterm-kitty`), WezTerm and
+  /// Ghostty (`$TERM_PROGRAM`), and SIXEL on a `foot`, `mlterm` or `contour` `$TERM`.
+  /// Terminals aren't asked, so elsewhere it falls back to cells.
+  pub fn protocol(self) -> Option<Protocol> {
+    match self {
+      GraphicsMode::Off => None,
+      GraphicsMode::Kitty => Some(Protocol::Kitty),
+      GraphicsMode::Sixel => Some(Protocol::Sixel),
+      GraphicsMode::Auto => {
+        let var = |name| std::env::var(name).unwrap_or_default();
+        let (term, program) = (var("TERM"), var("TERM_PROGRAM"));
+
+        if std::env::var_os("KITTY_WINDOW_ID").is_some()
+          || term == "xterm-kitty"
+          || program == "WezTerm"
+          || program == "ghostty"
+        {
+          Some(Protocol::Kitty)
+        } else if ["foot", "mlterm", "contour"]
+          .iter()
+          .any(|name| term.starts_with(name))
+        {
+          Some(Protocol::Sixel)
+        } else {
+          None
+        }
+      }
+    }
+  }
+}
+
+impl Display for GraphicsMode {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    f.write_str(match self {
+      GraphicsMode::Off => "off",
+      GraphicsMode::Auto => "auto",
+      GraphicsMode::Kitty => "kitty",
+      GraphicsMode::Sixel => "sixel",
+    })
+  }
+}
+
+impl FromStr for GraphicsMode {
+  type Err = anyhow::Error;
+
+  fn from_str(s: &str) -> anyhow::Result<Self> {
+    match s {
+      "off" => Ok(GraphicsMode::Off),
+      "auto" => Ok(GraphicsMode::Auto),
+      "kitty" => Ok(GraphicsMode::Kitty),
+      "sixel" => Ok(GraphicsMode::Sixel),
+      _ => anyhow::bail!("expected `off`, `auto`, `kitty` or `sixel`"),
+    }
+  }
+}
+
+/// Stand-in for the glow renderer in builds without the `graphics` feature, where
+/// `graphics` can only be `off`, so it is never constructed.
+#[cfg(not(feature = "graphics"))]
+pub enum Glow {}
+
+#[cfg(not(feature = "graphics"))]
+impl Glow {
+  pub fn new(_protocol: Protocol) -> Option<Self> {
+    None
+  }
+
+  pub fn present(
+    &mut self,
+    _frame: rmatrix::Frame,
+    _renderer: &mut crate::render::Renderer,
+    _out: &mut impl std::io::Write,
+  ) -> anyhow::Result<()> {
+    match *self {}
+  }
+}
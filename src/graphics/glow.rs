@@ -0,0 +1,318 @@
+use super::Protocol;
+use crate::render::Renderer;
+use crate::terminal;
+use crossterm::cursor::MoveTo;
+use crossterm::queue;
+use crossterm::style::Print;
+use rmatrix::crossterm_ext::ColorExt;
+use rmatrix::glyph::Glyph;
+use rmatrix::Frame;
+use std::io::Write;
+
+/// Pixels of the glow image per cell, across and down. The glow is blurry anyway,
+/// so it is rasterized coarsely and scaled up.
+const SUB_X: usize = 2;
+const SUB_Y: usize = 4;
+
+/// Strength of the light a glyph's color gives off, more for bold heads. Above 1 as
+/// the blur spreads a column's light over several times its width.
+const GLOW: f32 = 1.0;
+const HEAD_GLOW: f32 = 1.6;
+
+/// Radius of the box blur in image pixels, applied twice for a softer, rounder falloff.
+const BLUR_RADIUS: usize = 3;
+
+/// Id of the kitty image, every frame replaces the last one.
+const KITTY_ID: u32 = 1;
+
+/// Largest payload of a single kitty escape sequence, in base64 bytes.
+const KITTY_CHUNK: usize = 4096;
+
+/// Levels per color channel of the SIXEL palette, 6³ = 216 of the 256 registers.
+const SIXEL_LEVELS: u32 = 6;
+
+/// Draws a blurred image of the drops' colors beneath the glyphs, see [`crate::graphics`].
+pub struct Glow {
+  protocol: Protocol,
+  /// Size of a cell in screen pixels, SIXEL pictures are sent at full resolution.
+  cell: (usize, usize),
+}
+
+/// Pixels of a glow image, straight RGBA.
+struct Image {
+  width: usize,
+  height: usize,
+  rgba: Vec<u8>,
+}
+
+impl Glow {
+  /// `None` when the terminal doesn't report its size in pixels, which every
+  /// terminal that can show pictures does, so the rain is drawn in cells only.
+  pub fn new(protocol: Protocol) -> Option<Self> {
+    let size = crossterm::terminal::window_size().ok()?;
+    if size.width == 0 || size.height == 0 || size.columns == 0 || size.rows == 0 {
+      log::warn!("the terminal doesn't report its size in pixels, drawing without glow");
+      return None;
+    }
+
+    log::info!("drawing the glow with {:?}", protocol);
+    Some(Self {
+      protocol,
+      cell: (
+        (size.width / size.columns).max(1) as usize,
+        (size.height / size.rows).max(1) as usize,
+      ),
+    })
+  }
+
+  /// Emits the glow of `frame` and then the frame itself through `renderer`. Nothing
+  /// is sent while the frame on screen stays the same.
+  pub fn present(
+    &mut self,
+    frame: Frame,
+    renderer: &mut Renderer,
+    out: &mut impl Write,
+  ) -> anyhow::Result<()> {
+    if renderer.shown() == Some(&frame) {
+      return Ok(());
+    }
+
+    let image = rasterize(&frame);
+    queue!(out, MoveTo(0, 0))?;
+    match self.protocol {
+      Protocol::Kitty => {
+        write_kitty(&image, frame.width(), frame.height(), out)?;
+        renderer.present(frame, out)
+      }
+      Protocol::Sixel => {
+        write_sixel(&image, self.cell, out)?;
+        renderer.present_over_image(frame, out)
+      }
+    }
+  }
+}
+
+impl Drop for Glow {
+  fn drop(&mut self) {
+    // a picture on the main screen would outlive the run, text is cleared by the guard
+    if self.protocol == Protocol::Kitty {
+      let mut out = terminal::output();
+      let _ = write!(out, "\x1b_Ga=d,d=I,i={},q=2\x1b\\", KITTY_ID);
+      let _ = out.flush();
+    }
+  }
+}
+
+/// The glow of every glyph in `frame`, blurred, at [`SUB_X`] by [`SUB_Y`] pixels per cell.
+fn rasterize(frame: &Frame) -> Image {
+  let (width, height) = (
+    frame.width() as usize * SUB_X,
+    frame.height() as usize * SUB_Y,
+  );
+  let mut light = vec![[0.0_f32; 3]; width * height];
+
+  for (y, row) in frame.rows().enumerate() {
+    for (x, cell) in row.iter().enumerate() {
+      let Some((r, g, b)) = cell.fg.to_rgb().filter(|_| cell.glyph != Glyph::from(' ')) else {
+        continue;
+      };
+      let share = if cell.bold { HEAD_GLOW } else { GLOW };
+      let color = [r as f32 * share, g as f32 * share, b as f32 * share];
+      for sy in y * SUB_Y..(y + 1) * SUB_Y {
+        light[sy * width + x * SUB_X..sy * width + (x + 1) * SUB_X].fill(color);
+      }
+    }
+  }
+
+  for _ in 0..2 {
+    blur(&mut light, width, height, 1, width);
+    blur(&mut light, height, width, width, 1);
+  }
+
+  let mut rgba = Vec::with_capacity(width * height * 4);
+  for [r, g, b] in light {
+    // the hue at full strength, covering as much as the light is bright
+    let max = r.max(g).max(b).min(255.0);
+    if max < 1.0 {
+      rgba.extend([0, 0, 0, 0]);
+      continue;
+    }
+    let scale = 255.0 / r.max(g).max(b);
+    rgba.extend([
+      (r * scale) as u8,
+      (g * scale) as u8,
+      (b * scale) as u8,
+      max as u8,
+    ]);
+  }
+
+  Image {
+    width,
+    height,
+    rgba,
+  }
+}
+
+/// Box blur of [`BLUR_RADIUS`] along one axis: `lines` lines of `len` pixels, `step`
+/// apart within a line and `stride` apart from one line to the next.
+fn blur(light: &mut [[f32; 3]], len: usize, lines: usize, step: usize, stride: usize) {
+  let mut line = vec![[0.0_f32; 3]; len];
+  let window = (2 * BLUR_RADIUS + 1) as f32;
+
+  for l in 0..lines {
+    for (i, pixel) in line.iter_mut().enumerate() {
+      *pixel = light[l * stride + i * step];
+    }
+    let mut sum = [0.0_f32; 3];
+    // pixels past either end count as dark
+    for pixel in &line[..BLUR_RADIUS.min(len)] {
+      (0..3).for_each(|c| sum[c] += pixel[c]);
+    }
+    for i in 0..len {
+      if let Some(entering) = line.get(i + BLUR_RADIUS) {
+        (0..3).for_each(|c| sum[c] += entering[c]);
+      }
+      light[l * stride + i * step] = sum.map(|s| s / window);
+      if let Some(leaving) = i.checked_sub(BLUR_RADIUS).map(|j| line[j]) {
+        (0..3).for_each(|c| sum[c] -= leaving[c]);
+      }
+    }
+  }
+}
+
+/// Places `image` at the cursor, scaled to `columns` by `rows` cells beneath the text.
+fn write_kitty(image: &Image, columns: u16, rows: u16, out: &mut impl Write) -> anyhow::Result<()> {
+  let data = base64(&image.rgba);
+  let mut chunks = data.as_bytes().chunks(KITTY_CHUNK).peekable();
+  let mut first = true;
+
+  while let Some(chunk) = chunks.next() {
+    let more = chunks.peek().is_some() as u8;
+    // a chunk of base64 is ASCII
+    let chunk = std::str::from_utf8(chunk)?;
+    if first {
+      // z=-1 puts it under the text, C=1 keeps the cursor where it was
+      queue!(
+        out,
+        Print(format!(
+          "\x1b_Ga=T,f=32,s={},v={},c={},r={},i={},p=1,z=-1,C=1,q=2,m={};{}\x1b\\",
+          image.width, image.height, columns, rows, KITTY_ID, more, chunk
+        ))
+      )?;
+      first = false;
+    } else {
+      queue!(out, Print(format!("\x1b_Gm={};{}\x1b\\", more, chunk)))?;
+    }
+  }
+
+  Ok(())
+}
+
+/// Sends `image` as a SIXEL picture at the cursor, every image pixel scaled up to
+/// cover its share of a `cell` of screen pixels.
+///
+/// The last row of cells is left out, as a picture reaching the bottom of the screen
+/// scrolls it. Every pixel is painted, dark ones in black, so no glow of the last
+/// frame is left over.
+fn write_sixel(image: &Image, cell: (usize, usize), out: &mut impl Write) -> anyhow::Result<()> {
+  let (cell_w, cell_h) = cell;
+  let columns = image.width / SUB_X;
+  let rows = (image.height / SUB_Y).saturating_sub(1);
+  let width = columns * cell_w;
+  // whole bands of six pixel rows
+  let height = rows * cell_h / 6 * 6;
+  if width == 0 || height == 0 {
+    return Ok(());
+  }
+
+  // palette register of every screen pixel, glow over black
+  let register = |x: usize, y: usize| {
+    let i = (y * SUB_Y / cell_h) * image.width + x * SUB_X / cell_w;
+    let [r, g, b, a] = [0, 1, 2, 3].map(|c| image.rgba[i * 4 + c] as u32);
+    let level = |c: u32| (c * a / 255 * (SIXEL_LEVELS - 1) + 127) / 255;
+    (level(r) * SIXEL_LEVELS + level(g)) * SIXEL_LEVELS + level(b)
+  };
+  let registers: Vec<u32> = (0..height)
+    .flat_map(|y| (0..width).map(move |x| (x, y)))
+    .map(|(x, y)| register(x, y))
+    .collect();
+
+  let mut sixel = String::new();
+  // P2=1: pixels left out keep what is on screen, every one is painted anyway
+  sixel.push_str(&format!("\x1bP0;1;0q\"1;1;{};{}", width, height));
+  let mut used = vec![false; SIXEL_LEVELS.pow(3) as usize];
+  registers.iter().for_each(|&r| used[r as usize] = true);
+  for (index, _) in used.iter().enumerate().filter(|(_, used)| **used) {
+    let index = index as u32;
+    let percent = |level: u32| level * 100 / (SIXEL_LEVELS - 1);
+    sixel.push_str(&format!(
+      "#{};2;{};{};{}",
+      index,
+      percent(index / SIXEL_LEVELS / SIXEL_LEVELS),
+      percent(index / SIXEL_LEVELS % SIXEL_LEVELS),
+      percent(index % SIXEL_LEVELS)
+    ));
+  }
+
+  let mut bits = vec![0_u8; width];
+  for band in (0..height).step_by(6) {
+    let mut colors: Vec<u32> = registers[band * width..(band + 6) * width].to_vec();
+    colors.sort_unstable();
+    colors.dedup();
+
+    for (n, &color) in colors.iter().enumerate() {
+      for (x, bits) in bits.iter_mut().enumerate() {
+        *bits = (0..6)
+          .filter(|row| registers[(band + row) * width + x] == color)
+          .fold(0, |bits, row| bits | 1 << row);
+      }
+      if n > 0 {
+        // back to the start of the band for the next color
+        sixel.push('$');
+      }
+      sixel.push_str(&format!("#{}", color));
+      push_runs(&mut sixel, &bits);
+    }
+    sixel.push('-');
+  }
+  sixel.push_str("\x1b\\");
+
+  queue!(out, Print(sixel))?;
+  Ok(())
+}
+
+/// Appends the sixel chars for `bits`, runs of the same one shortened to `!count`.
+fn push_runs(sixel: &mut String, bits: &[u8]) {
+  let mut i = 0;
+  while i < bits.len() {
+    let run = bits[i..].iter().take_while(|&&b| b == bits[i]).count();
+    let c = (b'?' + bits[i]) as char;
+    if run > 3 {
+      sixel.push_str(&format!("!{}{}", run, c));
+    } else {
+      (0..run).for_each(|_| sixel.push(c));
+    }
+    i += run;
+  }
+}
+
+/// Standard base64 with padding, as the kitty protocol expects.
+fn base64(data: &[u8]) -> String {
+  const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+  let mut encoded = String::with_capacity(data.len().div_ceil(3) * 4);
+  for chunk in data.chunks(3) {
+    let n = chunk
+      .iter()
+      .enumerate()
+      .fold(0_u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+    for i in 0..4 {
+      if i <= chunk.len() {
+        encoded.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+      } else {
+        encoded.push('=');
+      }
+    }
+  }
+  encoded
+}
@@ -0,0 +1,165 @@
+use crate::keys::{Action, KeyMap};
+use crate::replay::Replay;
+use crossterm::event::{self, Event, KeyEventKind};
+use std::time::Duration;
+
+/// Where the main loop takes its input from, once per frame.
+///
+/// The loop only sees [`Event`]s, so the terminal can be swapped for a scripted
+/// sequence, like the log of a recording, to drive pause, quit and the other keys
+/// without a keyboard.
+pub trait EventSource {
+  /// Events due by `frame`, the frame the rain is about to render.
+  fn events(&mut self, frame: u64) -> anyhow::Result<Vec<Event>>;
+}
+
+/// Live input from the terminal.
+pub struct Live;
+
+impl EventSource for Live {
+  fn events(&mut self, _frame: u64) -> anyhow::Result<Vec<Event>> {
+    poll_events()
+  }
+}
+
+/// The recorded events, plus a quit key pressed on the real keyboard so a replay can
+/// be left early. Other real input is ignored, it would make the run diverge.
+pub struct Replaying {
+  pub replay: Replay,
+  pub keys: KeyMap,
+}
+
+impl EventSource for Replaying {
+  fn events(&mut self, frame: u64) -> anyhow::Result<Vec<Event>> {
+    let quit = poll_events()?
+      .into_iter()
+      .find(|e| matches!(e, Event::Key(key) if self.keys.resolve(key) == Some(Action::Quit)));
+    let mut events = self.replay.take(frame);
+    events.extend(quit);
+
+    Ok(events)
+  }
+}
+
+/// Drains all pending terminal events without blocking.
+pub fn poll_events() -> anyhow::Result<Vec<Event>> {
+  let mut read = Vec::new();
+  while event::poll(Duration::ZERO)? {
+    read.push(event::read()?);
+  }
+  Ok(coalesce(read))
+}
+
+/// The events of one frame as the main loop wants them: key releases and repeats
+/// left out, and only the last of several resizes.
+fn coalesce(read: impl IntoIterator<Item = Event>) -> Vec<Event> {
+  let mut events = Vec::new();
+  for event in read {
+    match event {
+      Event::Key(key) if key.kind != KeyEventKind::Press => {}
+      // dragging a window edge floods resizes, only the final size is worth a redraw
+      event @ Event::Resize(..) => {
+        events.retain(|e| !matches!(e, Event::Resize(..)));
+        events.push(event);
+      }
+      event => events.push(event),
+    }
+  }
+  events
+}
+
+/// Raw terminal events scripted by the frame they arrive on, passed on as
+/// [`poll_events`] would have read them.
+#[cfg(test)]
+pub struct MockEventSource {
+  script: Vec<(u64, Event)>,
+}
+
+#[cfg(test)]
+impl MockEventSource {
+  pub fn new(script: Vec<(u64, Event)>) -> Self {
+    Self { script }
+  }
+}
+
+#[cfg(test)]
+impl EventSource for MockEventSource {
+  fn events(&mut self, frame: u64) -> anyhow::Result<Vec<Event>> {
+    let (due, later) = self.script.drain(..).partition(|&(at, _)| at <= frame);
+    self.script = later;
+    Ok(coalesce(due.into_iter().map(|(_, event)| event)))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crossterm::event::{KeyCode, KeyEvent, KeyEventState, KeyModifiers};
+
+  fn key(code: KeyCode, kind: KeyEventKind) -> Event {
+    Event::Key(KeyEvent {
+      code,
+      modifiers: KeyModifiers::NONE,
+      kind,
+      state: KeyEventState::NONE,
+    })
+  }
+
+  /// Actions of the keys the source delivers on `frame`.
+  fn actions(source: &mut MockEventSource, frame: u64) -> Vec<Option<Action>> {
+    let keys = KeyMap::default();
+    source
+      .events(frame)
+      .unwrap()
+      .iter()
+      .filter_map(|event| match event {
+        Event::Key(key) => Some(keys.resolve(key)),
+        _ => None,
+      })
+      .collect()
+  }
+
+  #[test]
+  fn only_key_presses_are_handled() {
+    let mut source = MockEventSource::new(vec![
+      (0, key(KeyCode::Char(' '), KeyEventKind::Press)),
+      (0, key(KeyCode::Char(' '), KeyEventKind::Release)),
+      (1, key(KeyCode::Char('p'), KeyEventKind::Repeat)),
+      (1, key(KeyCode::Char('p'), KeyEventKind::Press)),
+      (1, key(KeyCode::Char('x'), KeyEventKind::Press)),
+    ]);
+
+    assert_eq!(actions(&mut source, 0), [Some(Action::Shockwave)]);
+    assert_eq!(actions(&mut source, 1), [Some(Action::Pause), None]);
+    assert!(source.events(2).unwrap().is_empty());
+  }
+
+  #[test]
+  fn resizes_of_a_frame_collapse_into_the_last() {
+    let mut source = MockEventSource::new(vec![
+      (0, Event::Resize(80, 24)),
+      (0, Event::FocusLost),
+      (0, Event::Resize(100, 30)),
+      (0, Event::Resize(120, 40)),
+      (1, Event::Resize(90, 20)),
+    ]);
+
+    assert_eq!(
+      source.events(0).unwrap(),
+      [Event::FocusLost, Event::Resize(120, 40)]
+    );
+    assert_eq!(source.events(1).unwrap(), [Event::Resize(90, 20)]);
+  }
+
+  #[test]
+  fn quit_keys_arrive_on_their_frame() {
+    let mut source = MockEventSource::new(vec![
+      (3, key(KeyCode::Char('q'), KeyEventKind::Release)),
+      (5, key(KeyCode::Esc, KeyEventKind::Press)),
+    ]);
+
+    // the main loop asks once per frame and stops at the first quit it resolves
+    let quit = (0..10).find(|&frame| actions(&mut source, frame).contains(&Some(Action::Quit)));
+    assert_eq!(quit, Some(5));
+  }
+}
@@ -0,0 +1,119 @@
+use crate::replay::{key_name, parse_key};
+use crossterm::event::KeyEvent;
+
+/// What a key press does in the main loop.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+  Quit,
+  /// Clears the canvas, only when there is one.
+  ClearCanvas,
+  Shockwave,
+  /// Freezes the rain, or lets it run again.
+  Pause,
+  /// Switches to the next built-in theme.
+  NextTheme,
+}
+
+impl Action {
+  pub const ALL: [Action; 5] = [
+    Action::Quit,
+    Action::ClearCanvas,
+    Action::Shockwave,
+    Action::Pause,
+    Action::NextTheme,
+  ];
+
+  /// Setting that binds the keys of the action.
+  pub fn setting(self) -> &'static str {
+    match self {
+      Action::Quit => "key-quit",
+      Action::ClearCanvas => "key-clear-canvas",
+      Action::Shockwave => "key-shockwave",
+      Action::Pause => "key-pause",
+      Action::NextTheme => "key-next-theme",
+    }
+  }
+
+  pub fn from_setting(setting: &str) -> Option<Action> {
+    Action::ALL
+      .into_iter()
+      .find(|action| action.setting() == setting)
+  }
+
+  fn default_keys(self) -> &'static [&'static str] {
+    match self {
+      Action::Quit => &["q", "esc", "ctrl+c"],
+      Action::ClearCanvas => &["c"],
+      Action::Shockwave => &["space"],
+      Action::Pause => &["p"],
+      Action::NextTheme => &["tab"],
+    }
+  }
+}
+
+/// Which keys trigger which [`Action`], keys are stored by their [`key_name`].
+#[derive(Clone)]
+pub struct KeyMap {
+  bindings: Vec<(String, Action)>,
+}
+
+impl Default for KeyMap {
+  fn default() -> Self {
+    let bindings = Action::ALL
+      .into_iter()
+      .flat_map(|action| {
+        action
+          .default_keys()
+          .iter()
+          .map(move |&key| (key.to_owned(), action))
+      })
+      .collect();
+
+    Self { bindings }
+  }
+}
+
+impl KeyMap {
+  /// Replaces the keys of `action` with the comma separated `keys`, e.g. `q,ctrl+c`.
+  /// An empty list unbinds the action. Keys bound elsewhere move over to `action`.
+  pub fn bind(&mut self, action: Action, keys: &str) -> anyhow::Result<()> {
+    let keys = keys
+      .split(',')
+      .map(str::trim)
+      .filter(|key| !key.is_empty())
+      .map(|key| {
+        let key = parse_key(key)?;
+        Ok(key_name(&key).expect("parsed keys are presses"))
+      })
+      .collect::<anyhow::Result<Vec<_>>>()?;
+
+    self
+      .bindings
+      .retain(|(key, bound)| *bound != action && !keys.contains(key));
+    self
+      .bindings
+      .extend(keys.into_iter().map(|key| (key, action)));
+
+    Ok(())
+  }
+
+  /// Keys of `action` in the format [`KeyMap::bind`] takes.
+  pub fn keys(&self, action: Action) -> String {
+    self
+      .bindings
+      .iter()
+      .filter(|(_, bound)| *bound == action)
+      .map(|(key, _)| key.as_str())
+      .collect::<Vec<_>>()
+      .join(",")
+  }
+
+  pub fn resolve(&self, key: &KeyEvent) -> Option<Action> {
+    let name = key_name(key)?;
+    self
+      .bindings
+      .iter()
+      .find(|(key, _)| *key == name)
+      .map(|&(_, action)| action)
+  }
+}
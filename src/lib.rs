@@ -0,0 +1,28 @@
+//! Matrix-style digital rain.
+//!
+//! The simulation in [`Rain`] is free of IO and timing, so it can be driven by
+//! any host loop: the `rmatrix` binary renders it to a terminal with crossterm,
+//! but the same cells could be painted onto a GUI or a browser canvas.
+//!
+//! Lifecycle events (startup, resizes, recycled drops, draining) are reported
+//! through the [`log`] facade and cost nothing unless a logger is installed.
+//! When rendering to a terminal, install one that writes to a file or to whichever
+//! of stdout and stderr isn't the render surface.
+
+pub mod banner;
+pub mod canvas;
+pub mod config;
+pub mod crossterm_ext;
+pub mod drop;
+pub mod frame;
+pub mod glyph;
+pub mod rain;
+pub mod reveal;
+pub mod shader;
+pub mod source;
+pub mod theme;
+
+pub use config::Config;
+pub use drop::FadeCurve;
+pub use frame::{Frame, FrameCell, FrameContext, Overlay};
+pub use rain::{Cell, LengthDistribution, Rain, RainBuilder, RainStyle, RecyclePolicy};
@@ -1,273 +1,679 @@
-use crate::crossterm_ext::ColorExt;
-use crossterm::cursor::{MoveDown, MoveLeft, MoveTo, SetCursorStyle};
-use crossterm::style::{Color, Print, SetForegroundColor};
+use crate::adaptive::Adaptive;
+use crate::cli::{Command, Options};
+use crate::countdown::Countdown;
+use crate::drift::Drift;
+use crate::eggs::EasterEggs;
+use crate::graphics::Glow;
+use crate::input::{EventSource, Live, Replaying};
+use crate::keys::{Action, KeyMap};
+use crate::profile::{Profile, Stage};
+use crate::render::Renderer;
+use crate::replay::{EventLog, Replay};
+use crate::state::State;
+use crate::stats::FrameStats;
+use crate::terminal::TerminalGuard;
+use crate::themes::Themes;
+use crate::tiles::Tiles;
+use crate::timing::Timing;
+use anyhow::Context;
+use crossterm::event::Event;
+use crossterm::execute;
 use crossterm::terminal::{size, Clear, ClearType};
-use crossterm::{cursor, execute, queue, ExecutableCommand, QueueableCommand};
-use rand::prelude::SliceRandom;
-use rand::Rng;
-use smart_default::SmartDefault;
-use std::cell::OnceCell;
+use rmatrix::{Frame, Rain};
 use std::io::{stdout, Write};
-use std::ops::Range;
-use std::sync::OnceLock;
-use std::thread::sleep;
-use std::time::Duration;
-
-mod crossterm_ext {
-  use crossterm::style::Color;
-
-  pub trait ColorExt {
-    fn rgb(r: u8, g: u8, b: u8) -> Color;
-  }
-
-  impl ColorExt for Color {
-    fn rgb(r: u8, g: u8, b: u8) -> Color {
-      Color::Rgb { r, g, b }
-    }
-  }
+use std::time::{Duration, Instant};
+
+mod adaptive;
+mod charset;
+mod cli;
+mod colors;
+mod countdown;
+mod doctor;
+mod drift;
+mod eggs;
+mod graphics;
+mod input;
+mod keys;
+mod profile;
+mod render;
+mod replay;
+mod signals;
+mod state;
+mod stats;
+mod terminal;
+mod themes;
+mod tiles;
+mod timing;
+
+fn is_broken_pipe(error: &anyhow::Error) -> bool {
+  error.chain().any(|cause| {
+    cause
+      .downcast_ref::<std::io::Error>()
+      .is_some_and(|e| e.kind() == std::io::ErrorKind::BrokenPipe)
+  })
 }
 
-fn get_random_char() -> char {
-  let mut rng = rand::thread_rng();
-
-  // Define the ASCII symbol ranges
-  let ranges = [
-    33..=47,   // ! " # $ % & ' ( ) * + , - . /
-    58..=64,   // : ; < = > ? @
-    65..=90,   // A-Z
-    91..=96,   // [ \ ] ^ _ `
-    97..=122,  // a-z
-    123..=126, // { | } ~
-  ];
-
-  // Generate a random symbol within the selected ranges
-  let random_symbol = ranges.choose(&mut rng).unwrap().clone();
-  rng.gen_range(random_symbol) as u8 as char
+/// Context of the errors of drawing a frame, which end the main loops.
+const DRAW_FAILED: &str = "failed to draw on the terminal";
+
+/// Optional per-frame add-ons of the main loop.
+struct Extras {
+  countdown: Option<Countdown>,
+  profile: Profile,
+  stats: Option<FrameStats>,
+  adaptive: Option<Adaptive>,
+  /// Interval between scene changes, where the field drains and starts over.
+  reshuffle: Option<Duration>,
+  eggs: Option<EasterEggs>,
+  /// Blank and redraw drops straight on the terminal instead of diffing whole frames.
+  direct_draw: bool,
+  /// The rain runs at a fixed size, resizes of the terminal only move its frame.
+  virtual_size: bool,
+  drift: Option<Drift>,
+  /// Random share of the frame delay every wait is lengthened or shortened by.
+  delay_jitter: f32,
+  themes: Themes,
+  /// Picture drawn under every frame, see [`graphics`].
+  glow: Option<Glow>,
 }
 
-fn get_all_unicode_chars() -> &'static [char] {
-  static ALL_UNICODE_SYMBOLS: OnceLock<Vec<char>> = OnceLock::new();
-
-  ALL_UNICODE_SYMBOLS.get_or_init(|| {
-    (33..=0x7F_u32)
-      .filter_map(|i| std::char::from_u32(i))
-      .filter(|c| !c.is_whitespace())
-      .collect()
-  })
+/// State of the main loop carried from one frame to the next.
+struct Session<'a> {
+  keys: &'a KeyMap,
+  extras: Extras,
+  renderer: Renderer,
+  /// Terminal size the virtual frame is centered in.
+  screen: (u16, u16),
+  start: Instant,
+  /// Counted in frame delays like the countdown, so replays reshuffle on the same frames.
+  until_reshuffle: Option<Duration>,
+  exiting: bool,
+  paused: bool,
+  /// While paused, the picture that was on screen: it is what gets redrawn, so repeated
+  /// redraws are identical even if the rain's own frame would have changed, e.g. dimmed
+  /// on losing focus.
+  frozen: Option<Frame>,
 }
 
-struct RainDropPart(char, Color);
-
-impl RainDropPart {
-  fn draw(&self) -> anyhow::Result<()> {
-    let mut stdout = stdout();
-    queue!(stdout, SetForegroundColor(self.1), Print(self.0))?;
-    Ok(())
+impl<'a> Session<'a> {
+  fn new(keys: &'a KeyMap, extras: Extras, screen: (u16, u16)) -> Self {
+    Self {
+      keys,
+      until_reshuffle: extras.reshuffle,
+      extras,
+      renderer: Renderer::default(),
+      screen,
+      start: Instant::now(),
+      exiting: false,
+      paused: false,
+      frozen: None,
+    }
   }
-}
 
-struct RainDrop {
-  length: u8,
-  color: Color,
-  speed: u8,
-  y: u16,
-  x: u16,
-}
+  /// One frame of the main loop: handles the events `source` has for it, advances
+  /// `rain` unless paused and draws it on `out`. Returns how long drawing took, `None`
+  /// once the run is over.
+  fn step(
+    &mut self,
+    rain: &mut Rain,
+    source: &mut dyn EventSource,
+    mut recording: Option<&mut EventLog>,
+    out: &mut impl Write,
+  ) -> anyhow::Result<Option<Duration>> {
+    let Extras {
+      countdown,
+      profile,
+      reshuffle,
+      eggs,
+      direct_draw,
+      virtual_size,
+      drift,
+      themes,
+      glow,
+      ..
+    } = &mut self.extras;
+    let (direct_draw, virtual_size) = (*direct_draw, *virtual_size);
+    // whether the screen was wiped this frame
+    let mut cleared = false;
+    for event in source
+      .events(rain.frame())
+      .context("failed to read input")?
+    {
+      if let Some(log) = recording.as_deref_mut() {
+        log.push(rain.frame(), self.start.elapsed(), &event);
+      }
 
-impl RainDrop {
-  fn get_parts(&self) -> Box<[RainDropPart]> {
-    let mut res: Vec<RainDropPart> = Vec::with_capacity(self.length as usize);
-
-    match self.color {
-      Color::Reset => {}
-      Color::Rgb { r, g, b } => {
-        let mut new_r = 0;
-        let mut new_g = 0;
-        let mut new_b = 0;
-
-        let decrement_step_r = r / self.length;
-        let decrement_step_g = g / self.length;
-        let decrement_step_b = b / self.length;
-
-        for i in 0..self.length {
-          res.push(RainDropPart(
-            self.get_char_for_part(i as usize),
-            Color::Rgb {
-              r: new_r,
-              g: new_g,
-              b: new_b,
-            },
-          ));
-
-          new_r = new_r.wrapping_add(decrement_step_r);
-          new_g = new_g.wrapping_add(decrement_step_g);
-          new_b = new_b.wrapping_add(decrement_step_b);
+      match event {
+        Event::Resize(w, h) => {
+          if virtual_size {
+            self.screen = (w, h);
+          } else {
+            let (w, h) = if drift.is_some() {
+              Drift::inner(w, h)
+            } else {
+              (w, h)
+            };
+            rain.resize(w, h);
+          }
+          execute!(out, Clear(ClearType::All)).context(DRAW_FAILED)?;
+          self.renderer.invalidate();
+          cleared = true;
         }
+        Event::Key(key) => match self.keys.resolve(&key) {
+          Some(Action::Quit) => return Ok(None),
+          Some(Action::ClearCanvas) if rain.canvas().is_some() => {
+            rain.clear_canvas();
+            execute!(out, Clear(ClearType::All)).context(DRAW_FAILED)?;
+            self.renderer.invalidate();
+            cleared = true;
+          }
+          Some(Action::Shockwave) => rain.shockwave(),
+          Some(Action::NextTheme) => {
+            let background = rain.config().background;
+            themes
+              .next(|key, value| rain.set(key, value))
+              .context("failed to switch to the next theme")?;
+            // the direct path leaves a background it no longer paints on screen
+            if direct_draw && rain.config().background != background {
+              execute!(out, Clear(ClearType::All)).context(DRAW_FAILED)?;
+              self.renderer.invalidate();
+              cleared = true;
+            }
+          }
+          Some(Action::Pause) => {
+            self.paused = !self.paused;
+            self.frozen = match self.renderer.shown() {
+              _ if !self.paused => None,
+              Some(shown) => Some(shown.clone()),
+              // the direct path keeps no frames, what it drew is the rain's current one
+              None if direct_draw => Some(rain.render_frame()),
+              None => None,
+            };
+          }
+          Some(_) => {}
+          None => {
+            if let Some(eggs) = eggs {
+              eggs.key(&key, rain);
+            }
+          }
+        },
+        Event::FocusGained => rain.set_focused(true),
+        Event::FocusLost => rain.set_focused(false),
+        _ => {}
+      }
+    }
+
+    if rain.is_drained() {
+      if self.exiting {
+        return Ok(None);
+      }
+      rain.restart();
+      self.until_reshuffle = *reshuffle;
+    }
+    profile.lap(Stage::Input);
+
+    let paused = self.paused;
+    if direct_draw && !paused {
+      if let Some(countdown) = countdown {
+        countdown.erase(rain, out).context(DRAW_FAILED)?;
+      }
+      if let Some(eggs) = eggs {
+        eggs.erase(rain, out).context(DRAW_FAILED)?;
+      }
+    }
+    profile.lap(Stage::Clear);
+    if !paused {
+      rain.tick();
+    }
+    profile.lap(Stage::Update);
+    let draw_start = Instant::now();
+    if direct_draw && paused {
+      // the screen keeps what was drawn before the pause, until it is wiped
+      if let Some(frozen) = self.frozen.as_ref().filter(|_| cleared) {
+        let (width, height) = rain.size();
+        render::draw_frame(&frozen.resized(width, height), None, out).context(DRAW_FAILED)?;
+      }
+    } else if direct_draw {
+      self.renderer.clear(rain, out).context(DRAW_FAILED)?;
+      profile.lap(Stage::Clear);
+      self.renderer.draw(rain, out).context(DRAW_FAILED)?;
+      if let Some(eggs) = eggs {
+        eggs.draw(rain, out).context(DRAW_FAILED)?;
+      }
+      if let Some(countdown) = countdown {
+        countdown.draw(rain, out).context(DRAW_FAILED)?;
       }
-      _ => {
-        for i in 0..self.length {
-          res.push(RainDropPart(self.get_char_for_part(i as usize), self.color));
+    } else {
+      let (width, height) = match (virtual_size, &drift) {
+        (true, _) => self.screen,
+        (false, Some(_)) => {
+          let (w, h) = rain.size();
+          (w + drift::MARGIN, h + drift::MARGIN)
         }
+        (false, None) => rain.size(),
+      };
+      let frame = match &self.frozen {
+        // laid out again for a resized screen, the rain itself stays where it was
+        Some(frozen) if virtual_size => frozen.centered(width, height),
+        Some(frozen) => frozen.resized(width, height),
+        None => {
+          let mut frame = rain.render_frame();
+          if let Some(eggs) = eggs {
+            eggs.paint(rain, &mut frame);
+          }
+          if let Some(countdown) = countdown {
+            countdown.paint(rain, &mut frame);
+          }
+          if virtual_size {
+            frame = frame.centered(width, height);
+          }
+          if let Some(drift) = drift {
+            let (x, y) = drift.offset(rain.elapsed());
+            frame = frame.placed(width, height, x as i32, y as i32);
+          }
+          if paused {
+            self.frozen = Some(frame.clone());
+          }
+          frame
+        }
+      };
+      match glow {
+        Some(glow) => glow
+          .present(frame, &mut self.renderer, out)
+          .context(DRAW_FAILED)?,
+        None => self.renderer.present(frame, out).context(DRAW_FAILED)?,
       }
     }
+    if let Some(countdown) = countdown.as_mut().filter(|_| !paused) {
+      if countdown.advance(rain.frame_delay()) {
+        rain.drain();
+        self.exiting = true;
+      }
+    }
+    if let Some(left) = self.until_reshuffle.as_mut().filter(|_| !paused) {
+      *left = left.saturating_sub(rain.frame_delay());
+      if left.is_zero() {
+        rain.drain();
+        self.until_reshuffle = None;
+      }
+    }
+    out.flush().context(DRAW_FAILED)?;
+    let draw = draw_start.elapsed();
+    profile.lap(Stage::Draw);
 
-    res.push(RainDropPart(
-      self.get_char_for_part(res.len()),
-      Color::White,
-    ));
+    Ok(Some(draw))
+  }
 
-    res.into_boxed_slice()
+  /// Whether nothing changes before the next input, so the loop waits for it instead
+  /// of pacing frames. Countdowns, reshuffles and drift are counted in frames, so
+  /// those keep the pace.
+  fn is_idle(&self, rain: &Rain) -> bool {
+    self.paused
+      || (rain.is_static()
+        && self.extras.countdown.is_none()
+        && self.until_reshuffle.is_none()
+        && self.extras.drift.is_none()
+        && self.extras.eggs.as_ref().is_none_or(EasterEggs::is_idle))
   }
+}
 
-  fn draw(&self) -> anyhow::Result<()> {
-    let (_, buffer_h) = size()?;
-    let mut stdout = stdout();
-
-    for (i, part) in self.get_parts().into_iter().enumerate().filter(|(i, _)| {
-      (0..buffer_h).contains(&(self.y + *i as u16).saturating_sub(self.length as u16))
-    }) {
-      queue!(
-        stdout,
-        MoveTo(
-          self.x,
-          (self.y + i as u16).saturating_sub(self.length as u16)
-        )
-      )?;
-
-      part.draw()?
+/// Main loop, events come from the terminal or, when replaying, from the recorded log.
+fn run(
+  rain: &mut Rain,
+  keys: &KeyMap,
+  source: &mut dyn EventSource,
+  mut recording: Option<&mut EventLog>,
+  timing: Timing,
+  extras: Extras,
+) -> anyhow::Result<()> {
+  let mut out = terminal::output();
+  let screen = size().context("failed to read the terminal size")?;
+  let mut session = Session::new(keys, extras, screen);
+
+  loop {
+    if signals::shutdown_requested() {
+      return Ok(());
+    }
+    let frame_start = Instant::now();
+    let Some(draw) = session.step(rain, source, recording.as_deref_mut(), &mut out)? else {
+      return Ok(());
+    };
+    if let Some(adaptive) = &mut session.extras.adaptive {
+      adaptive.frame(rain, frame_start.elapsed(), rain.frame_delay());
     }
 
-    Ok(())
+    if session.is_idle(rain) {
+      timing::idle().context("failed to wait for input")?;
+    } else {
+      let budget = timing::jittered(rain.frame_delay(), session.extras.delay_jitter);
+      timing::pace(timing, budget, frame_start);
+    }
+    session.extras.profile.lap(Stage::Sleep);
+    if let Some(stats) = &mut session.extras.stats {
+      stats.frame(rain, draw, frame_start.elapsed())?;
+    }
+    session.extras.profile.end_frame();
   }
+}
 
-  fn clear_tail(&self) -> anyhow::Result<()> {
-    let mut stdout = stdout();
-    for i in 0..self.speed {
-      queue!(
-        stdout,
-        MoveTo(self.x, self.y.saturating_sub(self.length as u16 + i as u16)),
-        Print(" ")
-      )?;
+/// Main loop of `--tiles`: one composited frame per tick, emitted where it differs
+/// from the one on screen.
+fn run_tiled(
+  tiles: &mut Tiles,
+  keys: &KeyMap,
+  timing: Timing,
+  delay_jitter: f32,
+  mut themes: Themes,
+) -> anyhow::Result<()> {
+  let mut out = terminal::output();
+  let mut shown: Option<Frame> = None;
+  let mut paused = false;
+
+  loop {
+    if signals::shutdown_requested() {
+      return Ok(());
+    }
+    let frame_start = Instant::now();
+    for event in input::poll_events().context("failed to read input")? {
+      match event {
+        Event::Resize(w, h) => {
+          tiles.resize(w, h);
+          execute!(out, Clear(ClearType::All)).context(DRAW_FAILED)?;
+          shown = None;
+        }
+        Event::Key(key) => match keys.resolve(&key) {
+          Some(Action::Quit) => return Ok(()),
+          Some(Action::ClearCanvas) => tiles.clear_canvas(),
+          Some(Action::Shockwave) => tiles.shockwave(),
+          Some(Action::Pause) => paused = !paused,
+          Some(Action::NextTheme) => themes
+            .next(|key, value| tiles.set(key, value))
+            .context("failed to switch to the next theme")?,
+          None => {}
+        },
+        Event::FocusGained => tiles.set_focused(true),
+        Event::FocusLost => tiles.set_focused(false),
+        _ => {}
+      }
     }
-    Ok(())
-  }
 
-  fn is_end(&self) -> anyhow::Result<bool> {
-    let (_, buffer_h) = size()?;
-    Ok((self.y.saturating_sub(self.length as u16)) > buffer_h)
-  }
+    // a paused wall is only composed again once a resize cleared the screen
+    if !paused || shown.is_none() {
+      if !paused {
+        tiles.tick();
+      }
+      let frame = tiles.compose();
+      render::draw_frame(&frame, shown.as_ref(), &mut out).context(DRAW_FAILED)?;
+      out.flush().context(DRAW_FAILED)?;
+      shown = Some(frame);
+    }
 
-  fn fall(&mut self) {
-    self.y = self.y + self.speed as u16
+    if paused {
+      timing::idle().context("failed to wait for input")?;
+    } else {
+      let budget = timing::jittered(tiles.frame_delay(), delay_jitter);
+      timing::pace(timing, budget, frame_start);
+    }
   }
+}
 
-  fn get_char_for_part(&self, i: usize) -> char {
-    let hash = self as *const Self as usize * 31 + (self.y as usize + i) * 31;
+/// Turns the outcome of a main loop into the exit result.
+fn finish(result: anyhow::Result<()>) -> anyhow::Result<()> {
+  match result {
+    // the terminal went away (e.g. its tab was closed), nothing left to report to
+    Err(e) if is_broken_pipe(&e) => Ok(()),
+    result => result,
+  }
+}
 
-    let all = get_all_unicode_chars();
-    all[hash % all.len()]
+fn main() -> anyhow::Result<()> {
+  let Options {
+    mut config,
+    record,
+    replay,
+    state_file,
+    timing,
+    delay_jitter,
+    countdown,
+    profile,
+    timing_csv,
+    adaptive,
+    min_drops,
+    reshuffle,
+    easter_eggs,
+    alt_screen,
+    render_to,
+    color_mode,
+    direct_draw,
+    keys,
+    tiles,
+    tile_layout,
+    tile_styles,
+    virtual_size,
+    drift,
+    themes,
+    graphics,
+    ..
+  } = match cli::parse_args(std::env::args().skip(1))? {
+    Command::Run(options) => *options,
+    Command::PrintConfig(options) => {
+      print!("{}", options.to_toml());
+      return Ok(());
+    }
+    Command::ShowCharset(options) => {
+      return match charset::print(&mut stdout(), options.config.glyphs.as_ref()) {
+        // piped into e.g. `head`, which stopped reading
+        Err(e) if is_broken_pipe(&e) => Ok(()),
+        result => result,
+      };
+    }
+    Command::Doctor(options) => {
+      doctor::print(&mut stdout(), &options)?;
+      return Ok(());
+    }
+    Command::Colors => {
+      colors::print(&mut stdout())?;
+      return Ok(());
+    }
+    Command::Help => {
+      cli::print_help();
+      return Ok(());
+    }
+  };
+
+  signals::install()?;
+  render::set_color_mode(color_mode);
+  let (mut width, mut height) = size()?;
+  if let Some(grid) = tiles {
+    let mut tiles = Tiles::new(&config, grid, tile_layout, &tile_styles, width, height);
+    let guard = TerminalGuard::new(alt_screen, render_to)?;
+    let result = run_tiled(&mut tiles, &keys, timing, delay_jitter, themes);
+    drop(guard);
+    return finish(result);
   }
 
-  fn new(length: u8, color: Color, x: u16) -> Self {
-    let mut rng = rand::thread_rng();
-    Self {
-      length,
-      color,
-      x,
-      y: rng.gen_range(1..8),
-      speed: rng.gen_range(1..3),
+  let mut source: Box<dyn EventSource> = match &replay {
+    Some(path) => {
+      let log = EventLog::load(path)?;
+      config.seed = Some(log.seed);
+      (width, height) = (log.width, log.height);
+      Box::new(Replaying {
+        replay: Replay::new(log),
+        keys: keys.clone(),
+      })
     }
+    None => Box::new(Live),
+  };
+  if let Some(size) = virtual_size {
+    (width, height) = size;
   }
-}
 
-enum RainStyle {
-  Solid(Color),
-  Rainbow,
-}
+  if let Some(gutter) = config.excluded_columns.iter().find(|g| g.end > width) {
+    anyhow::bail!(
+      "excluded columns {}:{} don't fit the {} columns wide terminal",
+      gutter.start,
+      gutter.end,
+      width
+    );
+  }
 
-struct Rain {
-  drops_count: usize,
-  drop_length_range: Range<u8>,
-  frame_delay: Duration,
-  style: RainStyle,
+  let resumed = state_file.as_deref().and_then(State::load);
+
+  let stats = timing_csv.as_deref().map(FrameStats::create).transpose()?;
+  // the log keeps the terminal's size, the rain is shrunk again on replay
+  let (w, h) = if drift.is_some() {
+    Drift::inner(width, height)
+  } else {
+    (width, height)
+  };
+  let mut rain = match &resumed {
+    Some(state) => {
+      log::info!("resuming at frame {}", state.frame);
+      state.resume(config, w, h)
+    }
+    None => Rain::new(config, w, h),
+  };
+  let mut log = record
+    .as_ref()
+    .map(|_| EventLog::new(rain.seed(), width, height));
+
+  log::info!("starting, alt screen: {}", alt_screen);
+  let guard = TerminalGuard::new(alt_screen, render_to)?;
+  let result = run(
+    &mut rain,
+    &keys,
+    source.as_mut(),
+    log.as_mut(),
+    timing,
+    Extras {
+      countdown: countdown.map(Countdown::new),
+      profile: Profile::new(profile),
+      stats,
+      adaptive: adaptive.then(|| Adaptive::new(min_drops)),
+      reshuffle,
+      eggs: easter_eggs.then(EasterEggs::default),
+      direct_draw,
+      virtual_size: virtual_size.is_some(),
+      drift: drift.map(Drift::new),
+      delay_jitter,
+      themes,
+      glow: graphics.protocol().and_then(Glow::new),
+    },
+  );
+  drop(guard);
+  log::info!("shutting down after {} frames", rain.frame());
+
+  if let (Some(path), Some(log)) = (&record, &log) {
+    log.save(path)?;
+  }
+  if let Some(path) = &state_file {
+    let state = State {
+      seed: rain.seed(),
+      frame: rain.frame(),
+    };
+    state.save(path)?;
+  }
 
-  drops: Vec<RainDrop>,
+  finish(result)
 }
 
-impl Rain {
-  fn new(
-    drops_count: usize,
-    drop_length: Range<u8>,
-    style: RainStyle,
-    frame_delay: Option<Duration>,
-  ) -> anyhow::Result<Self> {
-    let mut s = Self {
-      drops_count,
-      drop_length_range: drop_length,
-      style,
-      frame_delay: frame_delay.unwrap_or(Duration::from_millis(150)),
-      drops: Vec::with_capacity(drops_count),
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::input::MockEventSource;
+  use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+  use rmatrix::theme::THEMES;
+  use rmatrix::Config;
+
+  /// A loop with every add-on off.
+  fn session(keys: &KeyMap) -> Session<'_> {
+    let extras = Extras {
+      countdown: None,
+      profile: Profile::new(false),
+      stats: None,
+      adaptive: None,
+      reshuffle: None,
+      eggs: None,
+      direct_draw: false,
+      virtual_size: false,
+      drift: None,
+      delay_jitter: 0.0,
+      themes: Themes::default(),
+      glow: None,
     };
+    Session::new(keys, extras, (30, 12))
+  }
 
-    for _ in 0..drops_count {
-      s.add_new_drop()?;
-    }
+  fn rain() -> Rain {
+    let mut config = Config::default();
+    config.seed = Some(1);
+    Rain::new(config, 30, 12)
+  }
 
-    Ok(s)
+  fn press(code: KeyCode) -> Event {
+    Event::Key(KeyEvent::new(code, KeyModifiers::NONE))
   }
 
-  fn add_new_drop(&mut self) -> anyhow::Result<()> {
-    let (buffer_w, _) = size()?;
-    let mut rng = rand::thread_rng();
-
-    let len = rng.gen_range(self.drop_length_range.clone());
-    let x = rng.gen_range(0..buffer_w);
-
-    self.drops.push(match self.style {
-      RainStyle::Solid(color) => RainDrop::new(len, color, x),
-      RainStyle::Rainbow => RainDrop::new(
-        len,
-        Color::rgb(
-          rng.gen_range(0..255),
-          rng.gen_range(0..255),
-          rng.gen_range(0..255),
-        ),
-        x,
-      ),
-    });
-
-    Ok(())
+  /// Runs a frame of `session`, with the events `source` has for it.
+  fn step(session: &mut Session, rain: &mut Rain, source: &mut dyn EventSource) -> Vec<u8> {
+    let mut out = Vec::new();
+    assert!(session
+      .step(rain, source, None, &mut out)
+      .unwrap()
+      .is_some());
+    out
   }
 
-  fn draw(&mut self) -> anyhow::Result<()> {
-    let mut stdout = stdout();
-    execute!(stdout, Clear(ClearType::All), cursor::Hide, MoveTo(0, 0))?;
+  #[test]
+  fn a_paused_rain_stands_still() {
+    let keys = KeyMap::default();
+    let mut session = session(&keys);
+    let mut rain = rain();
+    let mut source = MockEventSource::new(vec![(3, press(KeyCode::Char('p')))]);
+    for _ in 0..3 {
+      step(&mut session, &mut rain, &mut source);
+    }
+    assert_eq!(rain.frame(), 3);
+
+    // the frame the pause key arrives on is the last one drawn
+    step(&mut session, &mut rain, &mut source);
+    assert!(session.is_idle(&rain));
+    let (frame, picture) = (rain.frame(), rain.render_frame());
+    for _ in 0..5 {
+      let out = step(&mut session, &mut rain, &mut source);
+      assert_eq!(rain.frame(), frame);
+      assert!(rain.render_frame() == picture);
+      assert!(out.is_empty(), "a paused frame is drawn again");
+    }
 
-    loop {
-      for i in 0..self.drops.len() {
-        self.drops[i].draw()?;
-        self.drops[i].fall();
-        self.drops[i].clear_tail();
+    let mut resume = MockEventSource::new(vec![(0, press(KeyCode::Char('p')))]);
+    step(&mut session, &mut rain, &mut resume);
+    assert_eq!(rain.frame(), frame + 1);
+  }
 
-        if self.drops[i].is_end()? {
-          self.drops.swap_remove(i);
-          self.add_new_drop()?;
-        }
+  #[test]
+  fn the_theme_key_switches_to_the_next_theme() {
+    let keys = KeyMap::default();
+    let mut session = session(&keys);
+    let mut rain = rain();
+    let tab = press(KeyCode::Tab);
+    let mut source = MockEventSource::new(vec![(2, tab.clone()), (5, tab)]);
+
+    for (frames, theme) in [(3, &THEMES[0]), (3, &THEMES[1])] {
+      for _ in 0..frames {
+        step(&mut session, &mut rain, &mut source);
       }
-      stdout.flush()?;
-
-      sleep(self.frame_delay)
+      let mut expected = Config::default();
+      expected.seed = Some(1);
+      for (key, value) in theme.settings {
+        expected.set(key, value).unwrap();
+      }
+      assert_eq!(
+        rain.config().entries(),
+        expected.entries(),
+        "{}",
+        theme.name
+      );
     }
   }
 }
-
-fn main() -> anyhow::Result<()> {
-  let mut rain = Rain::new(
-    80,
-    6..20,
-    RainStyle::Rainbow,
-    Some(Duration::from_millis(100)),
-  )?;
-
-  rain.draw()
-}
@@ -0,0 +1,83 @@
+use std::time::{Duration, Instant};
+
+/// Parts of a frame the main loop spends its time in.
+#[derive(Clone, Copy)]
+pub enum Stage {
+  Input,
+  Update,
+  Clear,
+  Draw,
+  Sleep,
+}
+
+const STAGES: [(Stage, &str); 5] = [
+  (Stage::Input, "input"),
+  (Stage::Update, "update"),
+  (Stage::Clear, "clear"),
+  (Stage::Draw, "draw"),
+  (Stage::Sleep, "sleep"),
+];
+
+/// How often the averages are reported.
+const REPORT_EVERY: Duration = Duration::from_secs(1);
+
+/// Per-stage frame timings, reported to stderr so they don't end up in the frame.
+/// Does nothing unless enabled.
+pub struct Profile {
+  enabled: bool,
+  totals: [Duration; STAGES.len()],
+  frames: u32,
+  lap: Instant,
+  since: Instant,
+}
+
+impl Profile {
+  pub fn new(enabled: bool) -> Self {
+    let now = Instant::now();
+    Self {
+      enabled,
+      totals: [Duration::ZERO; STAGES.len()],
+      frames: 0,
+      lap: now,
+      since: now,
+    }
+  }
+
+  /// Adds the time since the previous lap to `stage`.
+  pub fn lap(&mut self, stage: Stage) {
+    if !self.enabled {
+      return;
+    }
+
+    let now = Instant::now();
+    self.totals[stage as usize] += now - self.lap;
+    self.lap = now;
+  }
+
+  /// Counts a finished frame, printing the averages once a report is due.
+  pub fn end_frame(&mut self) {
+    if !self.enabled {
+      return;
+    }
+
+    self.frames += 1;
+    if self.since.elapsed() < REPORT_EVERY {
+      return;
+    }
+
+    let stages = STAGES
+      .iter()
+      .map(|&(stage, name)| {
+        let average = self.totals[stage as usize] / self.frames;
+        format!("{} {:.2}ms", name, average.as_secs_f64() * 1000.0)
+      })
+      .collect::<Vec<_>>()
+      .join(", ");
+    // raw mode doesn't translate `\n`, return the carriage explicitly
+    eprint!("profile: {} frames, {}\r\n", self.frames, stages);
+
+    self.totals = [Duration::ZERO; STAGES.len()];
+    self.frames = 0;
+    self.since = Instant::now();
+  }
+}
@@ -0,0 +1,1760 @@
+use crate::canvas::Canvas;
+use crate::config::Config;
+use crate::crossterm_ext::{named_color_to_rgb, ColorExt};
+use crate::drop::{mix, DrawContext, RainDrop, ShaderContext};
+use crate::frame::{Frame, FrameCell, FrameContext, Overlay};
+use crate::glyph::{get_all_unicode_chars, Charset, Glyph};
+use crate::reveal::RevealState;
+use crate::shader::Shader;
+use crate::source::Random;
+use crossterm::style::Color;
+use rand::rngs::{SmallRng, StdRng};
+use rand::{Rng, SeedableRng};
+use std::collections::HashMap;
+use std::iter::StepBy;
+use std::ops::Range;
+use std::time::Duration;
+
+#[derive(Clone)]
+pub enum RainStyle {
+  Solid(Color),
+  Rainbow,
+  /// Every drop cell is colored by a function of its position and the time, see [`crate::shader`].
+  Shader(Shader),
+}
+
+/// What happens when a drop leaves the screen.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum RecyclePolicy {
+  /// A new drop starts in a random column.
+  NewColumn,
+  /// A new drop starts in the same column, for continuous streams.
+  SameColumn,
+  /// The drop isn't replaced, the field thins out over time.
+  Off,
+}
+
+/// How drop lengths are picked from [`Config::drop_length_range`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LengthDistribution {
+  /// Every length in the range is equally likely.
+  Uniform,
+  /// Mostly lengths around `mean`, clamped to the range.
+  Normal { mean: f32, stddev: f32 },
+  /// Mostly short drops and a few long ones, `lambda` is the rate of the decay
+  /// per cell above the shortest length.
+  Exponential { lambda: f32 },
+}
+
+impl LengthDistribution {
+  /// A length from the non-empty `range`.
+  fn sample(self, range: Range<u8>, rng: &mut impl Rng) -> u8 {
+    let length = match self {
+      LengthDistribution::Uniform => return rng.gen_range(range),
+      LengthDistribution::Normal { mean, stddev } => {
+        // Box-Muller, `1 - u` keeps the logarithm finite
+        let (u, v): (f32, f32) = (rng.gen(), rng.gen());
+        mean + stddev * (-2.0 * (1.0 - u).ln()).sqrt() * (std::f32::consts::TAU * v).cos()
+      }
+      LengthDistribution::Exponential { lambda } => {
+        let u: f32 = rng.gen();
+        range.start as f32 - (1.0 - u).ln() / lambda
+      }
+    };
+
+    (length.round() as i32).clamp(range.start as i32, range.end as i32 - 1) as u8
+  }
+}
+
+/// A single screen cell produced by the simulation.
+pub struct Cell {
+  pub x: u16,
+  pub y: u16,
+  pub glyph: Glyph,
+  pub fg: Color,
+  /// `None` keeps the terminal's background.
+  pub bg: Option<Color>,
+  /// Drawn in bold, see [`Config::head_bold`].
+  pub bold: bool,
+}
+
+/// Frames an impact splash lasts, see [`Config::impact`].
+const SPLASH_FRAMES: u8 = 4;
+
+/// Splash on the bottom row where a drop's head hit it.
+struct Splash {
+  x: u16,
+  /// Frames since the impact.
+  age: u8,
+}
+
+/// Share of the way to white a flickering cell's color is raised, see [`Config::flicker_rate`].
+const FLICKER_BOOST: f32 = 0.6;
+
+/// Keeps the flicker draws apart from the accent ones.
+const FLICKER_SALT: u64 = 0xF11C_4E12;
+
+/// Glyph the letters of [`Config::banner`] are drawn with.
+const BANNER_GLYPH: char = '█';
+
+/// Brightness each layer of [`Config::column_layers`] keeps of the one in front of it.
+const LAYER_DIM: f32 = 0.45;
+
+/// Brightness of the random glyphs a [`Config::reveal`] char scrambles through.
+const SCRAMBLED: f32 = 0.6;
+
+/// Keeps the glyphs a [`Config::reveal`] scrambles through apart from the other rolls.
+const REVEAL_SALT: u64 = 0x5E_7E;
+
+/// Brightness of the rain around [`Config::banner`], so the letters stand out.
+const BEHIND_BANNER: f32 = 0.3;
+
+/// Background of the darker rows of [`Config::scanlines`] where nothing is painted.
+const SCANLINE_TINT: Color = Color::Rgb {
+  r: 24,
+  g: 24,
+  b: 24,
+};
+
+/// How much of a painted background is left on the darker rows of [`Config::scanlines`].
+const SCANLINE_DIM: f32 = 0.6;
+
+/// The rain simulation. It doesn't do any IO or timing on its own: the host calls
+/// [`Rain::tick`] once per frame and draws [`Rain::cells`] / [`Rain::vacated_cells`]
+/// however it likes, waiting [`Rain::frame_delay`] between frames.
+pub struct Rain {
+  config: Config,
+  seed: u64,
+  rng: StdRng,
+  /// Cached terminal size, only ever updated through [`Rain::resize`] so a frame
+  /// never sees a size that differs from the one drops were placed for.
+  width: u16,
+  height: u16,
+  frame: u64,
+  /// Sum of the frame delays of all ticks, so it matches between a run and its replay.
+  elapsed: Duration,
+  /// Whether the terminal has focus, terminals without focus reporting always count as focused.
+  focused: bool,
+  canvas: Option<Canvas>,
+  /// Extra speed multiplier of a running shockwave, decays back to 0.
+  boost: f32,
+  /// Set by [`Rain::drain`], drops that leave the screen are no longer replaced.
+  draining: bool,
+  /// Cells the drops left during the last tick, to be blanked by the renderer.
+  vacated: Vec<(u16, u16)>,
+  /// Row by row, whether a drop has passed over the cell, only tracked with a
+  /// [`Config::erase_color`].
+  washed: Vec<bool>,
+  /// Replacements of finished drops held back by [`Config::max_spawns_per_frame`],
+  /// with the column they are bound to.
+  pending: Vec<Option<u16>>,
+  /// Per column, the frame until which no drop spawns there after one finished,
+  /// only tracked with a [`Config::column_cooldown`].
+  cooldown_until: Vec<u64>,
+  /// Drawn in order on top of every [`Rain::render_frame`].
+  overlays: Vec<Overlay>,
+  /// Running impact splashes, only with a [`Config::impact`] glyph.
+  splashes: Vec<Splash>,
+  /// Glyphs of the drops left out of [`Config::text_share`].
+  filler: Random,
+
+  drops: Vec<RainDrop>,
+}
+
+impl Rain {
+  /// Starts the rain on a `width` x `height` screen with [`Config::drops_count`] drops,
+  /// or one per column in dense mode. Every drop starts in a column below `width` that
+  /// isn't excluded, with a length from [`Config::drop_length_range`]. Fewer drops are
+  /// placed only when no column is free, e.g. at a width of 0.
+  pub fn new(config: Config, width: u16, height: u16) -> Self {
+    let drops_count = config.drops_count;
+    let seed = config.seed.unwrap_or_else(rand::random);
+    let canvas = config
+      .canvas
+      .then(|| Canvas::new(width, height, config.canvas_decay));
+    let mut s = Self {
+      config,
+      seed,
+      rng: StdRng::seed_from_u64(seed),
+      width,
+      height,
+      frame: 0,
+      elapsed: Duration::ZERO,
+      focused: true,
+      canvas,
+      boost: 0.0,
+      draining: false,
+      vacated: Vec::new(),
+      washed: Vec::new(),
+      pending: Vec::new(),
+      cooldown_until: Vec::new(),
+      overlays: Vec::new(),
+      splashes: Vec::new(),
+      filler: Random::new(Charset::from_chars(get_all_unicode_chars())),
+      drops: Vec::with_capacity(drops_count),
+    };
+
+    s.warn_if_too_narrow();
+    s.fill();
+    s.stagger();
+    log::info!(
+      "rain started: seed {}, {}x{}, {} drops",
+      seed,
+      width,
+      height,
+      s.drops.len()
+    );
+
+    s
+  }
+
+  /// Adds an overlay that draws on top of every frame from [`Rain::render_frame`],
+  /// after the rain and earlier overlays. Clocks, text or whole widgets go here:
+  ///
+  /// ```ignore
+  /// let rain = rain.with_overlay(Box::new(|frame, ctx| {
+  ///   let secs = ctx.elapsed.as_secs();
+  ///   let text = format!(" {:02}:{:02} ", secs / 60, secs % 60);
+  ///   frame.put_str(ctx.width.saturating_sub(text.len() as u16), 0, &text, Color::White);
+  /// }));
+  /// ```
+  pub fn with_overlay(mut self, overlay: Overlay) -> Self {
+    self.overlays.push(overlay);
+    self
+  }
+
+  /// Builder starting from the default [`Config`] on an 80x24 screen.
+  pub fn builder() -> RainBuilder {
+    RainBuilder::default()
+  }
+
+  pub fn config(&self) -> &Config {
+    &self.config
+  }
+
+  /// Changes a setting while the rain runs, see [`Config::set`]. Meant for the look:
+  /// drops already falling keep their color and length, and nothing is re-validated.
+  pub fn set(&mut self, key: &str, value: &str) -> anyhow::Result<()> {
+    self.config.set(key, value)
+  }
+
+  /// Seed the simulation was started with, passing it back through [`Config::seed`] reproduces the run.
+  pub fn seed(&self) -> u64 {
+    self.seed
+  }
+
+  /// Number of ticks since the start.
+  pub fn frame(&self) -> u64 {
+    self.frame
+  }
+
+  pub fn size(&self) -> (u16, u16) {
+    (self.width, self.height)
+  }
+
+  /// Simulated time since the start, the sum of the frame delays so far.
+  pub fn elapsed(&self) -> Duration {
+    self.elapsed
+  }
+
+  /// Moves the frame count and the simulated time on to `frame`, as if that many
+  /// frames had passed at the configured delay, without simulating them. Effects that
+  /// follow the clock, like the hue cycle and shaders, then carry on from there.
+  pub fn skip_to(&mut self, frame: u64) {
+    self.frame = frame;
+    let seconds = self.config.frame_delay.as_secs_f64() * frame as f64;
+    self.elapsed = Duration::try_from_secs_f64(seconds).unwrap_or(Duration::MAX);
+  }
+
+  /// Drops on the field right now, dormant ones included.
+  pub fn drop_count(&self) -> usize {
+    self.drops.len()
+  }
+
+  pub fn canvas(&self) -> Option<&Canvas> {
+    self.canvas.as_ref()
+  }
+
+  /// Whether cells get a background of their own, from the canvas, the scanlines,
+  /// the erase color or the screen's background, see [`Rain::background`].
+  pub fn has_background(&self) -> bool {
+    self.canvas.is_some()
+      || self.config.scanlines
+      || self.config.erase_color.is_some()
+      || self.config.background != Color::Reset
+  }
+
+  /// Whether a drop has passed over `x, y` since the start or the last resize.
+  fn is_washed(&self, x: u16, y: u16) -> bool {
+    let i = y as usize * self.width as usize + x as usize;
+    self.washed.get(i).copied().unwrap_or(false)
+  }
+
+  pub fn clear_canvas(&mut self) {
+    if let Some(canvas) = &mut self.canvas {
+      canvas.clear();
+    }
+  }
+
+  pub fn set_focused(&mut self, focused: bool) {
+    self.focused = focused;
+  }
+
+  /// Sends a shockwave: every drop speeds up by [`Config::shockwave`] times and
+  /// settles back to normal over about a second.
+  pub fn shockwave(&mut self) {
+    self.boost = self.config.shockwave;
+  }
+
+  /// Changes [`Config::drops_count`], dropping the surplus drops right away.
+  pub fn set_drops_count(&mut self, count: usize) {
+    self.config.drops_count = count;
+    if !self.config.dense {
+      self.drops.truncate(count);
+    }
+    if self.recycles() {
+      self.fill();
+    }
+  }
+
+  /// Lets the drops on screen fall off without replacing them, drops that haven't
+  /// started falling yet are discarded right away.
+  pub fn drain(&mut self) {
+    log::info!("draining {} drops", self.drops.len());
+    self.draining = true;
+    self.pending.clear();
+    self.drops.retain(|drop| !drop.is_dormant());
+  }
+
+  /// Ends a [`Rain::drain`], filling the field with a fresh arrangement of drops.
+  pub fn restart(&mut self) {
+    log::info!("restarting the field");
+    self.draining = false;
+    self.fill();
+  }
+
+  /// Whether a [`Rain::drain`] finished and the screen is empty.
+  pub fn is_drained(&self) -> bool {
+    self.draining && self.drops.is_empty()
+  }
+
+  /// Whether ticking changes nothing on screen: no drops are left or about to spawn,
+  /// neither splashes nor the canvas are fading out, and no drain is waiting for a
+  /// restart.
+  pub fn is_static(&self) -> bool {
+    !self.draining
+      && self.drops.is_empty()
+      && self.pending.is_empty()
+      && self.splashes.is_empty()
+      && self.canvas.is_none()
+      && self
+        .config
+        .reveal
+        .as_ref()
+        .is_none_or(|reveal| reveal.is_done(self.frame))
+  }
+
+  /// Number of columns a drop can start in, so that its widest glyph still fits on screen.
+  ///
+  /// None when the widest glyph is wider than the screen, e.g. wide glyphs on a one
+  /// column terminal: the terminal would wrap such a glyph onto the next row, so the
+  /// screen stays blank until it is wide enough, see [`Rain::warn_if_too_narrow`].
+  fn spawn_columns(&self) -> u16 {
+    self
+      .width
+      .saturating_sub(self.config.glyphs.width() as u16 - 1)
+  }
+
+  /// Columns a drop can start in, a glyph's width apart so that the wide glyphs of
+  /// drops in neighbouring columns don't overlap.
+  fn drop_columns(&self) -> StepBy<Range<u16>> {
+    (0..self.spawn_columns()).step_by(self.config.glyphs.width() as usize)
+  }
+
+  /// Logs when no drop fits on the screen, see [`Rain::spawn_columns`].
+  fn warn_if_too_narrow(&self) {
+    if self.width > 0 && self.spawn_columns() == 0 {
+      log::warn!(
+        "{} column wide glyphs don't fit the {} column wide screen, nothing is drawn",
+        self.config.glyphs.width(),
+        self.width
+      );
+    }
+  }
+
+  /// Whether drops that leave the screen are replaced.
+  fn recycles(&self) -> bool {
+    !self.draining && self.config.recycle != RecyclePolicy::Off
+  }
+
+  /// Whether a drop in column `x` stays clear of the excluded columns, wide glyphs included,
+  /// and the column isn't cooling down after its last drop.
+  fn spawnable(&self, x: u16) -> bool {
+    let end = x + self.config.glyphs.width() as u16;
+    let excluded = self
+      .config
+      .excluded_columns
+      .iter()
+      .any(|gutter| gutter.start < end && x < gutter.end);
+    let cooling = self
+      .cooldown_until
+      .get(x as usize)
+      .is_some_and(|&until| until > self.frame);
+
+    !excluded && !cooling
+  }
+
+  /// Spawns a drop in `column`, or at a random allowed column of the cached width when `None`.
+  /// Returns `false` if no column is available, e.g. while the width is 0 or every
+  /// column is cooling down, or if `column` is cooling down.
+  fn add_new_drop(&mut self, column: Option<u16>) -> bool {
+    if column.is_some_and(|x| !self.spawnable(x)) {
+      return false;
+    }
+    let columns: Vec<u16> = self.drop_columns().filter(|&x| self.spawnable(x)).collect();
+    if columns.is_empty() {
+      return false;
+    }
+
+    let rng = &mut self.rng;
+    let lengths = self.config.drop_length_range.clone();
+    // `gen_range` panics on the empty range of a fixed length
+    let len = if lengths.is_empty() {
+      lengths.start
+    } else {
+      self.config.length_distribution.sample(lengths, rng)
+    };
+    // a drop longer than the screen would spend most of its life with the head past
+    // the bottom and the tail still trailing in, so it is cut to the screen's height
+    let len = len.min(self.height.clamp(1, u8::MAX as u16) as u8);
+    let x = column.unwrap_or_else(|| columns[rng.gen_range(0..columns.len())]);
+    debug_assert!(x < self.width);
+    let max_start_delay = self.config.max_start_delay;
+    let glitchy = rng.gen_bool(self.config.glitch_fraction as f64);
+    // only drawn for a share below 1, so runs without filler drops replay as before
+    let share = self.config.text_share;
+    let filler = share < 1.0 && !rng.gen_bool(share as f64);
+    let gravity = self.config.gravity;
+    // the frontmost layer the column is missing, or any when it is full
+    let layers = self.config.column_layers;
+    let layer = if layers > 1 {
+      let taken = |layer| {
+        self
+          .drops
+          .iter()
+          .any(|drop| drop.x() == x && drop.layer() == layer)
+      };
+      (0..layers)
+        .find(|&layer| !taken(layer))
+        .unwrap_or_else(|| rng.gen_range(0..layers))
+    } else {
+      0
+    };
+
+    let drop = match &self.config.style {
+      &RainStyle::Solid(color) => {
+        RainDrop::new(len, color, x, max_start_delay, glitchy, gravity, rng)
+      }
+      // the shader colors the cells, the drop's own color only shows on the canvas
+      RainStyle::Shader(shader) => {
+        let color = shader(x, 0, self.width, self.height, self.elapsed.as_secs_f32());
+        RainDrop::new(len, color, x, max_start_delay, glitchy, gravity, rng)
+      }
+      RainStyle::Rainbow => RainDrop::new(
+        len,
+        Color::rgb(
+          rng.gen_range(0..255),
+          rng.gen_range(0..255),
+          rng.gen_range(0..255),
+        ),
+        x,
+        max_start_delay,
+        glitchy,
+        gravity,
+        rng,
+      ),
+    };
+    let drop = match self.config.spawn_above {
+      Some(rows) => drop.spawned_above(rows),
+      None => drop,
+    };
+    let drop = if filler { drop.filler() } else { drop };
+    self.drops.push(drop.layered(layer));
+
+    true
+  }
+
+  /// Adopts a new terminal size. Only drops that no longer fit are replaced, the rest
+  /// and the canvas artwork carry over, and the same size again changes nothing.
+  pub fn resize(&mut self, width: u16, height: u16) {
+    if (width, height) == (self.width, self.height) {
+      return;
+    }
+
+    log::debug!("resized to {}x{}", width, height);
+    self.width = width;
+    self.height = height;
+    self.vacated.clear();
+    self.washed.clear();
+    if let Some(canvas) = &mut self.canvas {
+      canvas.resize(width, height);
+    }
+
+    self.warn_if_too_narrow();
+    let columns = self.spawn_columns();
+    self.drops.retain(|drop| drop.x() < columns);
+    self.splashes.retain(|splash| splash.x < columns);
+    self
+      .pending
+      .retain(|column| column.is_none_or(|x| x < columns));
+    if self.recycles() {
+      self.fill();
+    }
+  }
+
+  /// Spreads the initial drops over the top [`Config::fill`] share of the screen,
+  /// so it starts out that full instead of empty.
+  fn stagger(&mut self) {
+    let rows = (self.height as f32 * self.config.fill) as u16;
+    if rows == 0 {
+      return;
+    }
+
+    for drop in &mut self.drops {
+      drop.place(self.rng.gen_range(0..rows));
+    }
+  }
+
+  /// Tops the field up to [`Config::drops_count`] drops, or to one drop in every
+  /// free column, one per [`Config::column_layers`], in [`Config::dense`] mode.
+  fn fill(&mut self) {
+    if !self.config.dense {
+      while self.drops.len() < self.config.drops_count && self.add_new_drop(None) {}
+      return;
+    }
+
+    let layers = self.config.column_layers as usize;
+    for x in self.drop_columns() {
+      let held = self.drops.iter().filter(|drop| drop.x() == x).count();
+      for _ in held..layers {
+        if !self.spawnable(x) {
+          break;
+        }
+        self.add_new_drop(Some(x));
+      }
+    }
+  }
+
+  fn brightness(&self) -> f32 {
+    if self.focused {
+      1.0
+    } else {
+      self.config.unfocused_brightness
+    }
+  }
+
+  /// Brightness multiplier of the cell at `x, y`, falling off toward the nearest edge.
+  fn vignette(&self, x: u16, y: u16) -> f32 {
+    if self.config.vignette == 0.0 {
+      return 1.0;
+    }
+
+    // 0 on an edge, 1 in the middle of the axis
+    let closeness = |pos: u16, len: u16| {
+      let edge = pos.min(len.saturating_sub(1).saturating_sub(pos));
+      (edge as f32 / (len as f32 / 2.0).max(1.0)).min(1.0)
+    };
+    let centre = closeness(x, self.width).min(closeness(y, self.height));
+
+    1.0 - self.config.vignette * (1.0 - centre)
+  }
+
+  /// Whether the cell at `x, y` flashes the accent color this frame.
+  fn accented(&self, x: u16, y: u16) -> bool {
+    self.roll(x, y, 0) < self.config.accent_rate as f64
+  }
+
+  /// Whether the cell at `x, y` flares toward white this frame.
+  fn flickers(&self, x: u16, y: u16) -> bool {
+    self.roll(x, y, FLICKER_SALT) < self.config.flicker_rate as f64
+  }
+
+  /// Uniform `0..1` draw for the cell at `x, y` in the current frame, the same for the
+  /// same seed. Effects pass different `salt`s so they don't hit the same cells.
+  fn roll(&self, x: u16, y: u16, salt: u64) -> f64 {
+    let cell = (x as u64) << 16 | y as u64;
+    let roll = mix(self.seed ^ salt ^ mix(self.frame) ^ mix(cell));
+    // top 53 bits as a uniform float in 0..1
+    (roll >> 11) as f64 / (1u64 << 53) as f64
+  }
+
+  /// Delay before the next frame, stretched while unfocused to save CPU.
+  pub fn frame_delay(&self) -> Duration {
+    if self.focused {
+      self.config.frame_delay
+    } else {
+      self.config.frame_delay.div_f32(self.config.unfocused_fps)
+    }
+  }
+
+  /// Field-wide hue rotation for the current frame, derived from the frame count
+  /// rather than the wall clock so replays stay deterministic.
+  pub fn hue_shift(&self) -> f32 {
+    let Some(period) = self.config.hue_cycle else {
+      return 0.0;
+    };
+
+    let elapsed = self.config.frame_delay.as_secs_f32() * self.frame as f32;
+    360.0 * (elapsed / period.as_secs_f32()).fract()
+  }
+
+  fn draw_context(&self) -> DrawContext<'_> {
+    DrawContext {
+      glyphs: self.config.glyphs.as_ref(),
+      filler: &self.filler,
+      head_length: self.config.head_length,
+      head_color: self.config.head_color,
+      hue_shift: self.hue_shift(),
+      frame: self.frame,
+      stable_glyphs: self.config.stable_glyphs,
+      motion_blur: self.config.motion_blur,
+      trail_density: self.config.trail_density,
+      tail_dither: self.config.tail_dither,
+      fade_curve: self.config.fade_curve,
+      shader: match &self.config.style {
+        RainStyle::Shader(shader) => Some(ShaderContext {
+          shader,
+          width: self.width,
+          height: self.height,
+          seconds: self.elapsed.as_secs_f32(),
+        }),
+        _ => None,
+      },
+    }
+  }
+
+  /// Background of the cell at `x, y`, `Some(Color::Reset)` when backgrounds are
+  /// active but there is none there so the renderer knows to reset it. The canvas
+  /// is shown over the [`Config::erase_color`], both over the [`Config::background`],
+  /// and every other row is darker with [`Config::scanlines`].
+  pub fn background(&self, x: u16, y: u16) -> Option<Color> {
+    if !self.has_background() {
+      return None;
+    }
+    let level = self.brightness() * self.vignette(x, y);
+    let painted = self
+      .canvas
+      .as_ref()
+      .and_then(|canvas| canvas.color_at(x, y, level))
+      .or_else(|| {
+        let erase = self.config.erase_color?;
+        self.is_washed(x, y).then(|| erase.dimmed(level))
+      })
+      .or(Some(self.config.background).filter(|&bg| bg != Color::Reset));
+    let scanline = self.config.scanlines && y % 2 == 1;
+
+    let bg = match painted {
+      Some(bg) if scanline => bg.dimmed(SCANLINE_DIM),
+      Some(bg) => bg,
+      None if scanline => SCANLINE_TINT.dimmed(level),
+      None => return Some(Color::Reset),
+    };
+    Some(bg.tempered(self.config.temperature))
+  }
+
+  /// Advances every drop by one step, recycling the ones that left the screen.
+  pub fn tick(&mut self) {
+    self.vacated.clear();
+    // splashes are drawn anew every frame, so they are blanked like a drop's tail
+    let splashed: Vec<_> = self
+      .splash_cells()
+      .into_iter()
+      .map(|(x, y, _)| (x, y))
+      .collect();
+    self.vacated.extend(splashed);
+    self.splashes.iter_mut().for_each(|splash| splash.age += 1);
+    self.splashes.retain(|splash| splash.age < SPLASH_FRAMES);
+    let boost = 1.0 + self.boost;
+
+    let (gravity, max_speed, height) = (self.config.gravity, self.config.max_speed, self.height);
+    let bottom = height.saturating_sub(1);
+    let mut impacts = Vec::new();
+    for drop in &mut self.drops {
+      if drop.wait() {
+        continue;
+      }
+
+      let above = drop.head(height).is_some_and(|(_, y)| y < bottom);
+      drop.fall(gravity, max_speed, boost);
+      let x = drop.x();
+      if above && drop.head(height).is_none_or(|(_, y)| y == bottom) {
+        impacts.push(x);
+      }
+      self
+        .vacated
+        .extend(drop.vacated_rows().filter(|&y| y < height).map(|y| (x, y)));
+    }
+    if self.config.impact.is_some() {
+      self
+        .splashes
+        .extend(impacts.into_iter().map(|x| Splash { x, age: 0 }));
+    }
+
+    // removed and replaced only after the pass, swapping drops around or failing to
+    // spawn a replacement mid-pass would skip drops or index past the end
+    let mut ended = Vec::new();
+    let margin = self.config.recycle_margin;
+    let vacated = &mut self.vacated;
+    self.drops.retain(|drop| {
+      let end = drop.is_end(height) || margin.is_some_and(|m| drop.is_past(height, m));
+      if end {
+        ended.push(drop.x());
+        // what is left of a tail cut short by the margin
+        let x = drop.x();
+        vacated.extend(drop.covered_rows(height).map(|y| (x, y)));
+      }
+      !end
+    });
+    let cooldown = self.config.column_cooldown as u64;
+    if cooldown > 0 {
+      self.cooldown_until.resize(self.width as usize, 0);
+      for &x in &ended {
+        if let Some(until) = self.cooldown_until.get_mut(x as usize) {
+          *until = self.frame + cooldown;
+        }
+      }
+    }
+    if self.recycles() {
+      for x in ended {
+        log::debug!("recycling drop in column {}", x);
+        self.pending.push(match self.config.recycle {
+          RecyclePolicy::SameColumn => Some(x),
+          _ => None,
+        });
+      }
+
+      // spread bursts of replacements over the next frames, replacements without
+      // a free column wait for one to cool down
+      let mut spawns = 0;
+      for column in std::mem::take(&mut self.pending) {
+        if spawns < self.config.max_spawns_per_frame && self.add_new_drop(column) {
+          spawns += 1;
+        } else {
+          self.pending.push(column);
+        }
+      }
+    }
+
+    self.wash();
+    self.paint_canvas();
+    self.decay_boost();
+    self.frame += 1;
+    self.elapsed += self.frame_delay();
+  }
+
+  /// Marks the cells vacated this tick as washed, see [`Config::erase_color`].
+  fn wash(&mut self) {
+    if self.config.erase_color.is_none() {
+      return;
+    }
+
+    let width = self.width as usize;
+    self.washed.resize(width * self.height as usize, false);
+    for &(x, y) in &self.vacated {
+      if let Some(washed) = self.washed.get_mut(y as usize * width + x as usize) {
+        *washed = true;
+      }
+    }
+  }
+
+  /// Shrinks the shockwave boost to 1% over a second worth of frames.
+  fn decay_boost(&mut self) {
+    if self.boost == 0.0 {
+      return;
+    }
+
+    self.boost *= 0.01_f32.powf(self.config.frame_delay.as_secs_f32());
+    if self.boost < 0.01 {
+      self.boost = 0.0;
+    }
+  }
+
+  fn paint_canvas(&mut self) {
+    let hue_shift = self.hue_shift();
+    let Some(canvas) = &mut self.canvas else {
+      return;
+    };
+
+    canvas.fade();
+    for drop in &self.drops {
+      if let (Some((x, y)), Some(rgb)) = (drop.head(self.height), drop.shifted_color(hue_shift)) {
+        canvas.paint(x, y, rgb);
+      }
+    }
+  }
+
+  /// Cells blanked by the last tick, each is as wide as the widest glyph. Like
+  /// [`Rain::cells`] they lie within [`Rain::size`].
+  pub fn vacated_cells(&self) -> &[(u16, u16)] {
+    &self.vacated
+  }
+
+  /// Top left corner of [`Config::background_text`], centered unless positioned.
+  fn background_text_origin(&self, text: &[String]) -> (u16, u16) {
+    self.config.background_text_position.unwrap_or_else(|| {
+      let width = text
+        .iter()
+        .map(|line| line.chars().count())
+        .max()
+        .unwrap_or(0) as u16;
+      (
+        self.width.saturating_sub(width) / 2,
+        self.height.saturating_sub(text.len() as u16) / 2,
+      )
+    })
+  }
+
+  /// Whether `x, y` is one of the lit cells of [`Config::banner`], `None` away from
+  /// the banner. The banner is centered, so one that doesn't fit is clipped evenly
+  /// on both sides, and the rain within a cell of its bounds counts as behind it.
+  fn banner_cell(&self, x: u16, y: u16) -> Option<bool> {
+    let banner = self.config.banner.as_ref()?;
+    let left = (self.width as i32 - banner.width() as i32) / 2;
+    let top = (self.height as i32 - banner.height() as i32) / 2;
+    let (col, row) = (x as i32 - left, y as i32 - top);
+    let behind =
+      (-1..=banner.width() as i32).contains(&col) && (-1..=banner.height() as i32).contains(&row);
+
+    behind.then(|| banner.is_lit(col, row))
+  }
+
+  /// The char of [`Config::reveal`] at `x, y` and its column in the text, `None`
+  /// away from it and at its spaces. The text sits on the middle row, centered
+  /// like the banner.
+  fn reveal_cell(&self, x: u16, y: u16) -> Option<(usize, char)> {
+    let reveal = self.config.reveal.as_ref()?;
+    if y != self.height / 2 {
+      return None;
+    }
+    let left = (self.width as i32 - reveal.width() as i32) / 2;
+    let col = x as i32 - left;
+
+    reveal.char_at(col).map(|c| (col as usize, c))
+  }
+
+  /// Glyph and brightness of the [`Config::reveal`] char `c` at `x, y`, in column
+  /// `col` of the text, for the current frame.
+  fn reveal_glyph(&self, x: u16, y: u16, col: usize, c: char) -> (Glyph, f32) {
+    let reveal = self
+      .config
+      .reveal
+      .as_ref()
+      .expect("only called on reveal cells");
+    let scrambled = || {
+      let mut rng =
+        SmallRng::seed_from_u64(mix(self.seed ^ REVEAL_SALT ^ mix(self.frame)) ^ col as u64);
+      let glyph = self.config.glyphs.glyph(x, y, col, &mut rng);
+      // a wide glyph would spill into the next char
+      if glyph.width() == 1 {
+        glyph
+      } else {
+        Glyph::from(c)
+      }
+    };
+
+    match reveal.state(col, self.frame, self.seed) {
+      RevealState::Scrambling => (scrambled(), SCRAMBLED),
+      RevealState::Resolving if self.frame.is_multiple_of(2) => (Glyph::from(c), 1.0),
+      RevealState::Resolving => (scrambled(), SCRAMBLED),
+      RevealState::Locked => (Glyph::from(c), 1.0),
+    }
+  }
+
+  /// Non-space characters of the static background text, with their screen cells.
+  fn background_text(&self) -> Vec<(u16, u16, char)> {
+    let Some(text) = &self.config.background_text else {
+      return Vec::new();
+    };
+
+    let (left, top) = self.background_text_origin(text);
+    let mut chars = Vec::new();
+    for (row, line) in text.iter().enumerate() {
+      for (col, c) in line.chars().enumerate() {
+        let (x, y) = (left as usize + col, top as usize + row);
+        if c != ' ' && x < self.width as usize && y < self.height as usize {
+          chars.push((x as u16, y as u16, c));
+        }
+      }
+    }
+
+    chars
+  }
+
+  /// Every visible cell of the current frame in drawing order: the static background
+  /// text first, then the drops, which skip the cells the text occupies. Every cell
+  /// lies within [`Rain::size`], hosts don't need to clip.
+  pub fn cells(&self) -> Vec<Cell> {
+    let ctx = self.draw_context();
+    let brightness = self.brightness();
+    let mut cells = Vec::new();
+
+    let text = self.background_text();
+    for &(x, y, c) in &text {
+      cells.push(Cell {
+        x,
+        y,
+        glyph: Glyph::from(c),
+        fg: Color::White
+          .dimmed(brightness)
+          .tempered(self.config.temperature),
+        bg: self.background(x, y),
+        bold: false,
+      });
+    }
+
+    if self.config.banner.is_some() {
+      for y in 0..self.height {
+        for x in 0..self.width {
+          if self.banner_cell(x, y) == Some(true) {
+            cells.push(Cell {
+              x,
+              y,
+              glyph: Glyph::from(BANNER_GLYPH),
+              fg: Color::White
+                .dimmed(brightness)
+                .tempered(self.config.temperature),
+              bg: self.background(x, y),
+              bold: false,
+            });
+          }
+        }
+      }
+    }
+
+    // where drops of different layers overlap, index of the cell shown in `cells` and
+    // the priority of its drop
+    let mut layered: HashMap<(u16, u16), (usize, u8)> = HashMap::new();
+    // the text's row, none on a screen without rows
+    if self.config.reveal.is_some() && self.height > 0 {
+      let y = self.height / 2;
+      for x in 0..self.width {
+        if let Some((col, c)) = self.reveal_cell(x, y) {
+          let (glyph, level) = self.reveal_glyph(x, y, col, c);
+          cells.push(Cell {
+            x,
+            y,
+            glyph,
+            fg: Color::White
+              .dimmed(brightness * level)
+              .tempered(self.config.temperature),
+            bg: self.background(x, y),
+            bold: false,
+          });
+        }
+      }
+    }
+
+    // lowest priority first, so the cells of text drops are pushed over filler ones
+    let mut drawn: Vec<&RainDrop> = self
+      .drops
+      .iter()
+      .filter(|drop| !drop.is_dormant())
+      .collect();
+    drawn.sort_by_key(|drop| drop.priority());
+    for drop in drawn {
+      let x = drop.x();
+      let depth = LAYER_DIM.powi(drop.layer() as i32);
+      for (y, i, part) in drop.visible_parts(&ctx, self.height) {
+        if text.iter().any(|&(tx, ty, _)| (tx, ty) == (x, y)) || self.reveal_cell(x, y).is_some() {
+          continue;
+        }
+        let behind = match self.banner_cell(x, y) {
+          Some(true) => continue,
+          Some(false) => BEHIND_BANNER,
+          None => 1.0,
+        };
+
+        let fg = match self.config.accent_color {
+          Some(accent) if self.accented(x, y) => accent,
+          _ if self.flickers(x, y) => part.1.blend(Color::White, FLICKER_BOOST),
+          _ => part.1,
+        };
+        let mut fg = fg
+          .dimmed(brightness * behind * depth * self.vignette(x, y))
+          .tempered(self.config.temperature);
+        let bg = self.background(x, y);
+        if let Some(bg) = bg.filter(|_| self.config.tail_alpha > 0.0) {
+          // fully opaque at the head, most transparent at the tail
+          let along = i as f32 / drop.length() as f32;
+          fg = fg.blend(bg, self.config.tail_alpha * (1.0 - along).max(0.0));
+        }
+
+        // the bright head is the last `head_length` parts
+        let head = i + ctx.head_length as usize > drop.length() as usize;
+        let cell = Cell {
+          x,
+          y,
+          glyph: part.0,
+          fg,
+          bg,
+          bold: head && self.config.head_bold,
+        };
+        if self.config.column_layers == 1 {
+          cells.push(cell);
+          continue;
+        }
+        // the brighter of overlapping layers of the same priority shows, a drop of a
+        // higher one always comes later
+        let priority = drop.priority();
+        match layered.get(&(x, y)) {
+          Some(&(shown, ranked))
+            if ranked == priority && luminance(cells[shown].fg) >= luminance(fg) => {}
+          Some(&(shown, _)) => {
+            cells[shown] = cell;
+            layered.insert((x, y), (shown, priority));
+          }
+          None => {
+            layered.insert((x, y), (cells.len(), priority));
+            cells.push(cell);
+          }
+        }
+      }
+    }
+
+    if let Some(impact) = self.config.impact {
+      let color = named_color_to_rgb(self.config.accent_color.unwrap_or(Color::White));
+      for (x, y, level) in self.splash_cells() {
+        if self.banner_cell(x, y) == Some(true) || self.reveal_cell(x, y).is_some() {
+          continue;
+        }
+        cells.push(Cell {
+          x,
+          y,
+          glyph: Glyph::from(impact),
+          fg: Color::rgb(color.0, color.1, color.2)
+            .dimmed(brightness * level)
+            .tempered(self.config.temperature),
+          bg: self.background(x, y),
+          bold: false,
+        });
+      }
+    }
+
+    cells
+  }
+
+  /// Bottom row cells of the running splashes with their brightness. A splash starts
+  /// at the point of impact, spreads a glyph to each side and fades out.
+  fn splash_cells(&self) -> Vec<(u16, u16, f32)> {
+    let Some(y) = self.height.checked_sub(1) else {
+      return Vec::new();
+    };
+    let step = self.config.glyphs.width() as u16;
+    // last column a whole glyph fits in
+    let Some(last) = self.width.checked_sub(step) else {
+      return Vec::new();
+    };
+    let mut cells = Vec::new();
+
+    for splash in &self.splashes {
+      let level = 1.0 - splash.age as f32 / SPLASH_FRAMES as f32;
+      let mut xs = vec![splash.x];
+      if splash.age > 0 {
+        xs.extend(splash.x.checked_sub(step));
+        xs.extend(splash.x.checked_add(step));
+      }
+      cells.extend(xs.into_iter().filter(|&x| x <= last).map(|x| (x, y, level)));
+    }
+
+    cells
+  }
+
+  /// The whole screen for the current frame: canvas backgrounds with the drops on top.
+  pub fn render_frame(&self) -> Frame {
+    let mut frame = Frame::new(self.width, self.height);
+
+    if self.has_background() {
+      for y in 0..self.height {
+        for x in 0..self.width {
+          frame.set(x, y, FrameCell::blank(self.background(x, y)));
+        }
+      }
+    }
+
+    for cell in self.cells() {
+      frame.set(
+        cell.x,
+        cell.y,
+        FrameCell {
+          glyph: cell.glyph,
+          fg: cell.fg,
+          bg: cell.bg,
+          bold: cell.bold,
+        },
+      );
+    }
+
+    let ctx = FrameContext {
+      width: self.width,
+      height: self.height,
+      frame: self.frame,
+      elapsed: self.elapsed,
+    };
+    for overlay in &self.overlays {
+      overlay(&mut frame, ctx);
+    }
+
+    frame
+  }
+}
+
+/// Rough brightness of `color` to compare cells by, the terminal's default
+/// foreground counts as the brightest.
+fn luminance(color: Color) -> u32 {
+  color
+    .to_rgb()
+    .map_or(u32::MAX, |(r, g, b)| r as u32 + g as u32 + b as u32)
+}
+
+/// Step by step construction of a [`Rain`] for library users.
+///
+/// ```ignore
+/// let rain = Rain::builder()
+///   .size(width, height)
+///   .seed(42)
+///   .set("style", "solid:green")?
+///   .build()?;
+/// ```
+pub struct RainBuilder {
+  config: Config,
+  width: u16,
+  height: u16,
+}
+
+impl Default for RainBuilder {
+  fn default() -> Self {
+    Self {
+      config: Config::default(),
+      width: 80,
+      height: 24,
+    }
+  }
+}
+
+impl RainBuilder {
+  /// Starts from `config` instead of the defaults.
+  pub fn config(mut self, config: Config) -> Self {
+    self.config = config;
+    self
+  }
+
+  pub fn size(mut self, width: u16, height: u16) -> Self {
+    self.width = width;
+    self.height = height;
+    self
+  }
+
+  pub fn seed(mut self, seed: u64) -> Self {
+    self.config.seed = Some(seed);
+    self
+  }
+
+  /// Applies a setting by its CLI name, see [`Config::set`].
+  pub fn set(mut self, key: &str, value: &str) -> anyhow::Result<Self> {
+    self.config.set(key, value)?;
+    Ok(self)
+  }
+
+  /// Validates the settings and builds the rain.
+  pub fn build(self) -> anyhow::Result<Rain> {
+    self.config.validate()?;
+    Ok(Rain::new(self.config, self.width, self.height))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use proptest::prelude::{prop, proptest, ProptestConfig, Strategy};
+  use rand::rngs::SmallRng;
+
+  /// Screen sizes every generated config is run at, the degenerate ones first.
+  const SIZES: [(u16, u16); 7] = [(0, 0), (1, 1), (0, 5), (5, 0), (1, 30), (30, 1), (37, 13)];
+
+  /// Settings a fuzzed config draws from, each with the values it may take. The
+  /// values are small and few, so a failing case is easy to read off the message.
+  const SETTINGS: &[(&str, &[&str])] = &[
+    ("drops", &["0", "1", "5", "200"]),
+    ("dense", &["true", "false"]),
+    ("min-length", &["1", "3", "19"]),
+    ("max-length", &["7", "8", "255"]),
+    ("length", &["1", "255"]),
+    ("head-length", &["0", "1", "3"]),
+    ("gravity", &["0", "0.2", "5"]),
+    ("max-speed", &["0.5", "3", "300"]),
+    ("glitch-fraction", &["0", "0.5", "1"]),
+    ("trail-density", &["0", "0.5", "1"]),
+    ("column-layers", &["1", "4"]),
+    ("spawn-above", &["0", "3", "65535"]),
+    ("recycle-margin", &["0", "2", "65535"]),
+    ("recycle", &["new", "same", "off"]),
+    ("max-spawns-per-frame", &["1", "100"]),
+    ("column-cooldown", &["0", "5"]),
+    ("exclude", &["0:1", "2:5"]),
+    ("start-delay", &["0", "255"]),
+    ("charset", &["katakana", "digits", "ascii"]),
+    (
+      "glyphs",
+      &["matrix", "words", "connectors", "weighted:ア=3,0=1"],
+    ),
+    ("chars", &["01", "日本"]),
+    ("canvas", &["true", "false"]),
+    ("impact", &["0", "3"]),
+    ("vignette", &["0", "1"]),
+    ("scanlines", &["true"]),
+    ("erase-color", &["001500"]),
+    ("accent", &["ffffff"]),
+    ("accent-rate", &["0", "1"]),
+    ("reveal", &["WAKE UP"]),
+    ("banner", &["NEO"]),
+    ("motion-blur", &["true"]),
+    ("stable-glyphs", &["true"]),
+    ("text-share", &["0", "0.5"]),
+    ("tail-dither", &["0", "1", "4"]),
+  ];
+
+  /// Up to a dozen settings of [`SETTINGS`], shrinking toward fewer settings and
+  /// the first values of each.
+  fn settings() -> impl Strategy<Value = Vec<(&'static str, &'static str)>> {
+    let setting = (0..SETTINGS.len()).prop_flat_map(|i| {
+      let (key, values) = SETTINGS[i];
+      (0..values.len()).prop_map(move |j| (key, values[j]))
+    });
+    prop::collection::vec(setting, 0..12)
+  }
+
+  /// A valid config of `settings` applied one by one, those that leave it invalid
+  /// together with the ones before are dropped again.
+  fn fuzzed_config(
+    settings: &[(&'static str, &'static str)],
+  ) -> (Config, Vec<(&'static str, &'static str)>) {
+    let mut config = Config::default();
+    let mut applied = Vec::new();
+    for &(key, value) in settings {
+      let mut trial = config.clone();
+      if trial.set(key, value).is_ok() && trial.validate().is_ok() {
+        config = trial;
+        applied.push((key, value));
+      }
+    }
+    (config, applied)
+  }
+
+  fn assert_in_bounds(rain: &Rain, case: &str) {
+    let (width, height) = rain.size();
+    for cell in rain.cells() {
+      assert!(
+        cell.x < width && cell.y < height,
+        "cell {},{} outside {}x{}: {}",
+        cell.x,
+        cell.y,
+        width,
+        height,
+        case
+      );
+    }
+    for &(x, y) in rain.vacated_cells() {
+      assert!(
+        x < width && y < height,
+        "vacated {},{} outside {}x{}: {}",
+        x,
+        y,
+        width,
+        height,
+        case
+      );
+    }
+    let frame = rain.render_frame();
+    assert_eq!((frame.width(), frame.height()), (width, height), "{}", case);
+  }
+
+  #[test]
+  fn new_places_the_configured_drops() {
+    for seed in 0..200 {
+      let mut config = Config::default();
+      config.seed = Some(seed);
+      config.drops_count = 30;
+      let lengths = config.drop_length_range.clone();
+      let rain = Rain::new(config, 80, 24);
+
+      assert_eq!(rain.drops.len(), 30, "seed {}", seed);
+      for drop in &rain.drops {
+        assert!(drop.x() < 80, "seed {}: column {}", seed, drop.x());
+        assert!(
+          (lengths.start..=lengths.end).contains(&drop.length()),
+          "seed {}: length {} outside {:?}",
+          seed,
+          drop.length(),
+          lengths
+        );
+      }
+    }
+  }
+
+  #[test]
+  fn new_handles_degenerate_sizes_and_configs() {
+    let configs: [&[(&str, &str)]; 6] = [
+      &[],
+      &[("drops", "0")],
+      &[("drops", "10000")],
+      &[("length", "255")],
+      &[("dense", "true")],
+      &[("exclude", "0:3"), ("chars", "日")],
+    ];
+    for settings in configs {
+      for (width, height) in SIZES {
+        let mut config = Config::default();
+        for (key, value) in settings {
+          config.set(key, value).unwrap();
+        }
+        config.validate().unwrap();
+        config.seed = Some(1);
+        let rain = Rain::new(config, width, height);
+
+        let case = format!("{:?} at {}x{}", settings, width, height);
+        for drop in &rain.drops {
+          assert!(drop.x() < width, "{}", case);
+          assert!(drop.length() as u16 <= height.max(1), "{}", case);
+        }
+        assert_in_bounds(&rain, &case);
+      }
+    }
+  }
+
+  #[test]
+  fn last_row_fills_like_the_others() {
+    let (width, height) = (40, 12);
+    let mut config = Config::default();
+    config.seed = Some(3);
+    let mut rain = Rain::new(config, width, height);
+    let mut filled = vec![0_u32; height as usize];
+    for _ in 0..2000 {
+      rain.tick();
+      for cell in rain.cells() {
+        filled[cell.y as usize] += 1;
+      }
+    }
+
+    let middle = filled[height as usize / 2];
+    let last = filled[height as usize - 1];
+    assert!(
+      last * 10 >= middle * 8,
+      "last row {} vs middle row {}: {:?}",
+      last,
+      middle,
+      filled
+    );
+  }
+
+  proptest! {
+    #![proptest_config(ProptestConfig::with_cases(150))]
+
+    #[test]
+    fn fuzzed_configs_stay_in_bounds(
+      settings in settings(),
+      seed in 0..1000_u64,
+      sizes in prop::array::uniform4(0..SIZES.len()),
+    ) {
+      let (mut config, applied) = fuzzed_config(&settings);
+      config.seed = Some(seed);
+      let (width, height) = SIZES[sizes[0]];
+      let case = format!("seed {} at {}x{} with {:?}", seed, width, height, applied);
+
+      let mut rain = Rain::new(config, width, height);
+      assert_in_bounds(&rain, &case);
+      for frame in 0..60 {
+        rain.tick();
+        if frame % 20 == 19 {
+          let (width, height) = SIZES[sizes[frame / 20 + 1]];
+          rain.resize(width, height);
+        }
+        assert_in_bounds(&rain, &case);
+      }
+    }
+  }
+
+  #[test]
+  fn message_drops_show_over_random_ones() {
+    for layers in ["1", "3"] {
+      let mut config = Config::default();
+      config.set("glyphs", "message:ΑΒΓΔ").unwrap();
+      config.set("text-share", "0.5").unwrap();
+      config.set("column-layers", layers).unwrap();
+      config.drops_count = 60;
+      config.seed = Some(7);
+      // few columns, so the two kinds of drops keep running into each other
+      let mut rain = Rain::new(config, 6, 30);
+
+      let mut overlaps = 0;
+      for _ in 0..200 {
+        rain.tick();
+        let frame = rain.render_frame();
+        let ctx = rain.draw_context();
+        let shown = rain.drops.iter().filter(|drop| !drop.is_dormant());
+        let filler: Vec<(u16, u16)> = shown
+          .clone()
+          .filter(|drop| drop.priority() == 0)
+          .flat_map(|drop| {
+            let x = drop.x();
+            drop
+              .visible_parts(&ctx, rain.height)
+              .map(move |(y, _, _)| (x, y))
+          })
+          .collect();
+        for drop in shown.filter(|drop| drop.priority() > 0) {
+          for (y, _, part) in drop.visible_parts(&ctx, rain.height) {
+            let (x, glyph) = (drop.x(), part.0.to_string());
+            if !filler.contains(&(x, y)) || glyph.is_ascii() {
+              continue;
+            }
+            overlaps += 1;
+            let drawn = frame.get(x, y).unwrap().glyph.to_string();
+            assert!(
+              !drawn.is_ascii(),
+              "{:?} over the message at {},{}",
+              drawn,
+              x,
+              y
+            );
+          }
+        }
+      }
+      assert!(overlaps > 0, "no drops overlapped with {} layers", layers);
+    }
+  }
+
+  #[test]
+  fn drops_spawn_within_the_cached_width() {
+    for (chars, sizes) in [
+      ("ab", [(7, 9), (1, 4), (12, 3)]),
+      ("日本", [(7, 9), (2, 4), (3, 3)]),
+    ] {
+      let mut config = Config::default();
+      config.set("chars", chars).unwrap();
+      config.set("drops", "50").unwrap();
+      config.set("max-length", "3").unwrap();
+      config.seed = Some(3);
+      let mut rain = Rain::new(config, 40, 10);
+      for (width, height) in sizes {
+        rain.resize(width, height);
+        for _ in 0..50 {
+          rain.tick();
+          // a glyph starting in the last column would be cut off by the edge
+          let last = width - rain.config.glyphs.width() as u16;
+          assert!(
+            rain.drops.iter().all(|drop| drop.x() <= last),
+            "{} at {}x{}",
+            chars,
+            width,
+            height
+          );
+        }
+      }
+    }
+  }
+
+  #[test]
+  fn fixed_lengths_give_every_drop_that_length() {
+    for dist in ["uniform", "normal:5:2", "exp:0.5"] {
+      let mut config = Config::default();
+      config.set("length", "12").unwrap();
+      config.set("length-dist", dist).unwrap();
+      config.validate().unwrap();
+      assert_eq!(config.drop_length_range, 12..12);
+      config.seed = Some(5);
+
+      let mut rain = Rain::new(config, 30, 40);
+      for _ in 0..100 {
+        rain.tick();
+        assert!(
+          rain.drops.iter().all(|drop| drop.length() == 12),
+          "{}",
+          dist
+        );
+      }
+    }
+  }
+
+  #[test]
+  fn drops_ending_on_the_same_frame_are_all_replaced() {
+    let mut config = Config::default();
+    for (key, value) in [
+      ("drops", "30"),
+      ("length", "2"),
+      ("gravity", "1"),
+      ("max-speed", "1"),
+      ("start-delay", "0"),
+      ("max-spawns-per-frame", "100"),
+    ] {
+      config.set(key, value).unwrap();
+    }
+    config.seed = Some(11);
+    let mut rain = Rain::new(config, 10, 6);
+    // every drop in step, so they all leave the screen on the same tick
+    rain.drops.iter_mut().for_each(|drop| drop.place(0));
+    for _ in 0..8 {
+      rain.tick();
+    }
+    // heads two rows below the screen, tops on its bottom edge
+    assert!(rain.drops.iter().all(|drop| drop.is_past(6, 2)));
+
+    rain.tick();
+    // replacements start on screen
+    assert_eq!(rain.drops.len(), 30);
+    assert!(rain.drops.iter().all(|drop| !drop.is_past(6, 2)));
+  }
+
+  #[test]
+  fn drops_longer_than_the_screen_render_and_recycle() {
+    let mut config = Config::default();
+    config.set("length", "40").unwrap();
+    config.set("drops", "3").unwrap();
+    config.seed = Some(4);
+    let height = 5;
+    let mut rain = Rain::new(config, 8, height);
+    assert!(rain.drops.iter().all(|drop| drop.length() == height as u8));
+
+    let mut drawn = vec![false; height as usize];
+    for _ in 0..100 {
+      rain.tick();
+      rain
+        .cells()
+        .iter()
+        .for_each(|cell| drawn[cell.y as usize] = true);
+      assert_eq!(rain.drops.len(), 3);
+    }
+    assert!(drawn.iter().all(|&row| row), "{:?}", drawn);
+
+    // without replacements, every drop has to leave the screen on its own
+    rain.drain();
+    // the longest start delay, then the fall through the screen and the drop's length
+    for _ in 0..30 + 2 * height {
+      rain.tick();
+    }
+    assert!(rain.drops.is_empty());
+  }
+
+  #[test]
+  fn repeated_resizes_keep_the_field_consistent() {
+    let mut config = Config::default();
+    config.set("canvas", "true").unwrap();
+    config.set("drops", "40").unwrap();
+    config.seed = Some(8);
+    let mut rain = Rain::new(config, 30, 12);
+    let mut rng = SmallRng::seed_from_u64(8);
+
+    for _ in 0..200 {
+      let (width, height) = (rng.gen_range(0..40), rng.gen_range(0..20));
+      rain.resize(width, height);
+      rain.tick();
+
+      assert_eq!(rain.size(), (width, height));
+      assert!(rain.drops.iter().all(|drop| drop.x() < width));
+      let canvas = rain.canvas().unwrap();
+      assert_eq!((canvas.width(), canvas.height()), (width, height));
+      assert_in_bounds(&rain, &format!("{}x{}", width, height));
+      // drops are only missing where there is no column to put them
+      if width > 0 {
+        assert_eq!(rain.drops.len() + rain.pending.len(), 40);
+      }
+    }
+
+    // the same size again leaves the drops where they are
+    let heads: Vec<_> = rain
+      .drops
+      .iter()
+      .map(|drop| (drop.x(), drop.head(20)))
+      .collect();
+    let size = rain.size();
+    rain.resize(size.0, size.1);
+    let after: Vec<_> = rain
+      .drops
+      .iter()
+      .map(|drop| (drop.x(), drop.head(20)))
+      .collect();
+    assert_eq!(heads, after);
+  }
+
+  #[test]
+  fn wide_glyphs_on_a_narrower_screen_draw_nothing() {
+    for dense in ["false", "true"] {
+      let mut config = Config::default();
+      config.set("chars", "日本語").unwrap();
+      config.set("dense", dense).unwrap();
+      config.seed = Some(2);
+      let mut rain = Rain::new(config, 1, 10);
+      for _ in 0..50 {
+        rain.tick();
+        assert!(rain.drops.is_empty());
+        assert!(rain.cells().is_empty());
+      }
+
+      // a column wide enough brings the rain back
+      rain.resize(2, 10);
+      for _ in 0..50 {
+        rain.tick();
+      }
+      assert!(!rain.cells().is_empty());
+      assert!(
+        rain.cells().iter().all(|cell| cell.x == 0),
+        "a glyph overflows the edge"
+      );
+    }
+  }
+
+  #[test]
+  fn a_recycle_margin_replaces_long_drops_sooner() {
+    // frames until every drop of the first wave has finished
+    let lifetime = |margin: Option<&str>| {
+      let mut config = Config::default();
+      for (key, value) in [
+        ("drops", "10"),
+        ("length", "20"),
+        ("gravity", "1"),
+        ("max-speed", "1"),
+        ("start-delay", "0"),
+      ] {
+        config.set(key, value).unwrap();
+      }
+      if let Some(margin) = margin {
+        config.set("recycle-margin", margin).unwrap();
+      }
+      config.seed = Some(12);
+      let mut rain = Rain::new(config, 20, 20);
+      rain.drain();
+      rain.drops.iter_mut().for_each(|drop| drop.place(0));
+      (1..).find(|_| {
+        rain.tick();
+        rain.drops.is_empty()
+      })
+    };
+
+    assert_eq!(lifetime(None), Some(41));
+    assert_eq!(lifetime(Some("0")), Some(20));
+    assert_eq!(lifetime(Some("3")), Some(23));
+  }
+
+  #[test]
+  fn heads_are_bold_unless_turned_off() {
+    for bold in [true, false] {
+      let mut config = Config::default();
+      config.set("head-bold", &bold.to_string()).unwrap();
+      config.set("head-length", "2").unwrap();
+      config.seed = Some(1);
+      let mut rain = Rain::new(config, 30, 15);
+      for _ in 0..10 {
+        rain.tick();
+      }
+
+      let cells = rain.cells();
+      let mut checked = 0;
+      for drop in rain.drops.iter().filter(|drop| !drop.is_dormant()) {
+        let Some((x, y)) = drop.head(rain.height) else {
+          continue;
+        };
+        // where another drop crosses the head, either may show
+        let crossed = rain
+          .drops
+          .iter()
+          .filter(|other| other.x() == x && !other.is_dormant());
+        if crossed
+          .filter(|other| other.covered_rows(rain.height).contains(&y))
+          .count()
+          > 1
+        {
+          continue;
+        }
+        let head = cells
+          .iter()
+          .rev()
+          .find(|cell| (cell.x, cell.y) == (x, y))
+          .unwrap();
+        assert_eq!(head.bold, bold);
+        checked += 1;
+      }
+      assert!(checked > 5, "only {} heads on screen", checked);
+      // only the heads, never the tails
+      let bolds = cells.iter().filter(|cell| cell.bold).count();
+      assert!(bolds <= 2 * rain.drops.len());
+      assert_eq!(bolds > 0, bold);
+    }
+  }
+
+  #[test]
+  fn wide_glyphs_of_neighbouring_drops_dont_overlap() {
+    for dense in ["false", "true"] {
+      let mut config = Config::default();
+      config.set("chars", "日本語").unwrap();
+      config.set("dense", dense).unwrap();
+      config.set("drops", "40").unwrap();
+      config.seed = Some(5);
+      let mut rain = Rain::new(config, 21, 12);
+      for frame in 0..100 {
+        rain.tick();
+        assert!(
+          rain.drops.iter().all(|drop| drop.x() % 2 == 0),
+          "dense {}, frame {}: a drop between the columns",
+          dense,
+          frame
+        );
+        // every glyph starts in an even column, the odd ones hold its right halves
+        assert!(rain.cells().iter().all(|cell| cell.x % 2 == 0));
+      }
+    }
+  }
+
+  #[test]
+  fn head_color_and_background_are_drawn() {
+    let mut config = Config::default();
+    config.set("style", "solid:00ff00").unwrap();
+    config.set("accent-rate", "0").unwrap();
+    config.set("head-color", "ff0000").unwrap();
+    config.set("background", "102030").unwrap();
+    config.seed = Some(4);
+    let mut rain = Rain::new(config, 30, 15);
+    for _ in 0..10 {
+      rain.tick();
+    }
+
+    let heads: Vec<_> = rain.cells().into_iter().filter(|cell| cell.bold).collect();
+    assert!(!heads.is_empty());
+    assert!(heads.iter().all(|cell| cell.fg == Color::rgb(255, 0, 0)));
+    let frame = rain.render_frame();
+    assert!(frame
+      .rows()
+      .flatten()
+      .all(|cell| cell.bg == Some(Color::rgb(16, 32, 48))));
+
+    // the terminal's own background is left alone
+    rain.config.background = Color::Reset;
+    assert!(!rain.has_background());
+    assert!(rain
+      .render_frame()
+      .rows()
+      .flatten()
+      .all(|cell| cell.bg.is_none()));
+  }
+
+  #[test]
+  fn every_fuzzed_value_is_accepted_on_its_own() {
+    for &(key, values) in SETTINGS {
+      for value in values {
+        let mut config = Config::default();
+        config
+          .set(key, value)
+          .and_then(|()| config.validate())
+          .unwrap_or_else(|e| panic!("`{} = {}` is never fuzzed: {}", key, value, e));
+      }
+    }
+  }
+}
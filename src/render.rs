@@ -0,0 +1,337 @@
+use crossterm::cursor::MoveTo;
+use crossterm::queue;
+use crossterm::style::{
+  Attribute, Color, Print, ResetColor, SetAttribute, SetBackgroundColor, SetForegroundColor,
+};
+use rmatrix::crossterm_ext::ColorExt;
+use rmatrix::glyph::Glyph;
+use rmatrix::{Frame, Rain};
+use std::fmt::{Display, Formatter};
+use std::io::Write;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+/// Color depth every color is emitted in, see [`set_color_mode`].
+static COLOR_MODE: OnceLock<ColorMode> = OnceLock::new();
+
+/// How many colors the terminal can show, colors beyond it are mapped to the nearest
+/// one it has.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum ColorMode {
+  /// Picked from the environment, see [`ColorMode::detect`].
+  #[default]
+  Auto,
+  /// 24-bit colors as computed.
+  Truecolor,
+  /// The nearest color of the xterm 256-color palette.
+  Ansi256,
+  /// The nearest of the 16 named colors, which the terminal's theme may restyle.
+  Ansi16,
+  /// No colors at all, everything in the terminal's default colors.
+  Mono,
+}
+
+impl ColorMode {
+  /// Truecolor when `COLORTERM` says so, 256 colors for a `*-256color` `TERM`, 16 on
+  /// the Linux console and none on a `dumb` terminal. Anything else gets truecolor,
+  /// which most terminals support without advertising it.
+  pub fn detect() -> Self {
+    let var = |name| std::env::var(name).unwrap_or_default();
+    let (colorterm, term) = (var("COLORTERM"), var("TERM"));
+
+    if colorterm == "truecolor" || colorterm == "24bit" {
+      ColorMode::Truecolor
+    } else if term.ends_with("256color") {
+      ColorMode::Ansi256
+    } else if term == "linux" {
+      ColorMode::Ansi16
+    } else if term == "dumb" {
+      ColorMode::Mono
+    } else {
+      ColorMode::Truecolor
+    }
+  }
+
+  fn apply(self, color: Color) -> Color {
+    match self {
+      ColorMode::Auto | ColorMode::Truecolor => color,
+      ColorMode::Ansi256 => color.to_ansi256(),
+      ColorMode::Ansi16 => color.to_ansi16(),
+      ColorMode::Mono => Color::Reset,
+    }
+  }
+}
+
+impl Display for ColorMode {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    f.write_str(match self {
+      ColorMode::Auto => "auto",
+      ColorMode::Truecolor => "truecolor",
+      ColorMode::Ansi256 => "256",
+      ColorMode::Ansi16 => "16",
+      ColorMode::Mono => "mono",
+    })
+  }
+}
+
+impl FromStr for ColorMode {
+  type Err = anyhow::Error;
+
+  fn from_str(s: &str) -> anyhow::Result<Self> {
+    match s {
+      "auto" => Ok(ColorMode::Auto),
+      "truecolor" => Ok(ColorMode::Truecolor),
+      "256" => Ok(ColorMode::Ansi256),
+      "16" => Ok(ColorMode::Ansi16),
+      "mono" => Ok(ColorMode::Mono),
+      _ => anyhow::bail!("expected `auto`, `truecolor`, `256`, `16` or `mono`"),
+    }
+  }
+}
+
+/// Sets the color depth for the rest of the run, `Auto` is resolved right away.
+pub fn set_color_mode(mode: ColorMode) {
+  let mode = match mode {
+    ColorMode::Auto => ColorMode::detect(),
+    mode => mode,
+  };
+  log::info!("color mode: {}", mode);
+  let _ = COLOR_MODE.set(mode);
+}
+
+/// Foreground color command in the color mode of the run.
+pub fn set_fg(color: Color) -> SetForegroundColor {
+  SetForegroundColor(COLOR_MODE.get().map_or(color, |mode| mode.apply(color)))
+}
+
+/// Background color command in the color mode of the run.
+pub fn set_bg(color: Color) -> SetBackgroundColor {
+  SetBackgroundColor(COLOR_MODE.get().map_or(color, |mode| mode.apply(color)))
+}
+
+/// Draws the simulation onto a terminal.
+///
+/// By default whole frames are composed in memory and only the cells that differ
+/// from the frame on screen are emitted, see [`Renderer::present`]. [`Renderer::clear`]
+/// and [`Renderer::draw`] are the older direct path kept for debugging.
+#[derive(Default)]
+pub struct Renderer {
+  /// Background currently shown on screen for each cell, so only changed cells are emitted.
+  background_shown: Vec<Option<Color>>,
+  /// Last frame [`Renderer::present`] emitted.
+  shown: Option<Frame>,
+}
+
+impl Renderer {
+  /// Forgets what is on screen, to be called after the terminal was cleared.
+  pub fn invalidate(&mut self) {
+    self.background_shown.clear();
+    self.shown = None;
+  }
+
+  /// The frame last emitted by [`Renderer::present`], unless the screen was cleared since.
+  pub fn shown(&self) -> Option<&Frame> {
+    self.shown.as_ref()
+  }
+
+  /// Emits the cells of `frame` that differ from the frame on screen.
+  ///
+  /// The direct path blanks vacated cells and then repaints the drops, so on a slow
+  /// terminal a cell a drop still covers can show up blank for a moment, which
+  /// reads as flicker. Here every cell is written once with its final content, or
+  /// not at all when it didn't change.
+  pub fn present(&mut self, frame: Frame, out: &mut impl Write) -> anyhow::Result<()> {
+    draw_frame(&frame, self.shown.as_ref(), out)?;
+    self.shown = Some(frame);
+    Ok(())
+  }
+
+  /// Like [`Renderer::present`], right after a picture was drawn over the whole screen:
+  /// every glyph is emitted again on top of it, blank cells are left showing it.
+  #[cfg(feature = "graphics")]
+  pub fn present_over_image(&mut self, frame: Frame, out: &mut impl Write) -> anyhow::Result<()> {
+    // a stand-in for the screen where only the blank cells already match
+    let mut shown = Frame::new(frame.width(), frame.height());
+    for y in 0..frame.height() {
+      for x in 0..frame.width() {
+        if let (Some(cell), Some(on_screen)) = (frame.get(x, y), shown.get_mut(x, y)) {
+          if cell.glyph == Glyph::from(' ') {
+            *on_screen = cell.clone();
+          }
+        }
+      }
+    }
+
+    draw_frame(&frame, Some(&shown), out)?;
+    self.shown = Some(frame);
+    Ok(())
+  }
+
+  /// Blanks the cells vacated by the last tick, as wide as the widest glyph so wide glyphs are fully erased.
+  pub fn clear(&self, rain: &Rain, out: &mut impl Write) -> anyhow::Result<()> {
+    let blank = " ".repeat(rain.config().glyphs.width() as usize);
+
+    for &(x, y) in rain.vacated_cells() {
+      queue!(out, MoveTo(x, y))?;
+      if let Some(bg) = rain.background(x, y) {
+        queue!(out, set_bg(bg))?;
+      }
+      queue!(out, Print(&blank))?;
+    }
+
+    Ok(())
+  }
+
+  /// Draws the backgrounds and the drops of the current frame, after [`Renderer::clear`].
+  pub fn draw(&mut self, rain: &Rain, out: &mut impl Write) -> anyhow::Result<()> {
+    if rain.has_background() {
+      self.draw_background(rain, out)?;
+    }
+
+    for cell in rain.cells() {
+      queue!(out, MoveTo(cell.x, cell.y))?;
+      if let Some(bg) = cell.bg {
+        queue!(out, set_bg(bg))?;
+      }
+      queue!(out, set_fg(cell.fg))?;
+      print_glyph(out, &cell.glyph, cell.bold)?;
+    }
+
+    if rain.has_background() {
+      queue!(out, ResetColor)?;
+    }
+
+    Ok(())
+  }
+
+  /// Emits the cell backgrounds whose color changed since the last frame.
+  fn draw_background(&mut self, rain: &Rain, out: &mut impl Write) -> anyhow::Result<()> {
+    let (width, height) = rain.size();
+    let len = width as usize * height as usize;
+    if self.background_shown.len() != len {
+      self.background_shown = vec![None; len];
+    }
+
+    for y in 0..height {
+      for x in 0..width {
+        let color = rain.background(x, y).filter(|&c| c != Color::Reset);
+        let shown = &mut self.background_shown[y as usize * width as usize + x as usize];
+        if *shown == color {
+          continue;
+        }
+        *shown = color;
+
+        queue!(
+          out,
+          MoveTo(x, y),
+          set_bg(color.unwrap_or(Color::Reset)),
+          Print(' ')
+        )?;
+      }
+    }
+
+    Ok(())
+  }
+}
+
+/// Prints `glyph` at the cursor, in bold when `bold`. Bold is switched off right
+/// after, so it doesn't carry over to the next cell.
+fn print_glyph(out: &mut impl Write, glyph: &Glyph, bold: bool) -> anyhow::Result<()> {
+  if bold {
+    queue!(
+      out,
+      SetAttribute(Attribute::Bold),
+      Print(glyph),
+      SetAttribute(Attribute::NormalIntensity)
+    )?;
+  } else {
+    queue!(out, Print(glyph))?;
+  }
+  Ok(())
+}
+
+/// Emits the cells of `frame` that differ from `shown`, the frame currently on screen.
+pub fn draw_frame(
+  frame: &Frame,
+  shown: Option<&Frame>,
+  out: &mut impl Write,
+) -> anyhow::Result<()> {
+  for (y, row) in frame.rows().enumerate() {
+    // columns still covered by the last wide glyph
+    let mut covered = 0;
+    for (x, cell) in row.iter().enumerate() {
+      if covered > 0 {
+        covered -= 1;
+        continue;
+      }
+      covered = cell.glyph.width().saturating_sub(1);
+
+      let (x, y) = (x as u16, y as u16);
+      if shown.and_then(|shown| shown.get(x, y)) == Some(cell) {
+        continue;
+      }
+
+      queue!(
+        out,
+        MoveTo(x, y),
+        set_bg(cell.bg.unwrap_or(Color::Reset)),
+        set_fg(cell.fg)
+      )?;
+      print_glyph(out, &cell.glyph, cell.bold)?;
+    }
+  }
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn a_paused_frame_redraws_identically() {
+    let mut config = rmatrix::Config::default();
+    config.seed = Some(9);
+    let mut rain = Rain::new(config, 20, 8);
+    for _ in 0..10 {
+      rain.tick();
+    }
+    let frozen = rain.render_frame();
+
+    // what the main loop emits for the paused picture after the screen was wiped
+    let redraw = |renderer: &mut Renderer| {
+      renderer.invalidate();
+      let mut out = Vec::new();
+      renderer.present(frozen.resized(20, 8), &mut out).unwrap();
+      out
+    };
+    let mut renderer = Renderer::default();
+    let first = redraw(&mut renderer);
+    // the rain moving on underneath, or dimming for lost focus, doesn't show
+    rain.set_focused(false);
+    rain.tick();
+    let second = redraw(&mut renderer);
+
+    assert!(!first.is_empty());
+    assert_eq!(first, second);
+    let mut out = Vec::new();
+    renderer.present(frozen.resized(20, 8), &mut out).unwrap();
+    assert!(out.is_empty(), "an unchanged frame is emitted again");
+  }
+
+  #[test]
+  fn bold_ends_with_its_cell() {
+    let mut frame = Frame::new(3, 1);
+    frame.put_str(0, 0, "abc", Color::Green);
+    frame.get_mut(1, 0).unwrap().bold = true;
+
+    let mut out = Vec::new();
+    draw_frame(&frame, None, &mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    // bold on right before `b`, normal intensity right after it
+    let b = out.find('b').unwrap();
+    assert!(out[..b].ends_with("\x1b[1m"), "{:?}", out);
+    assert!(out[b + 1..].starts_with("\x1b[22m"), "{:?}", out);
+    assert_eq!(out.matches("\x1b[1m").count(), 1, "{:?}", out);
+  }
+}
@@ -0,0 +1,473 @@
+//! Recording and replaying of input events, so a run can be reproduced exactly.
+//!
+//! A log stores the seed, the initial terminal size and every resize/focus/key event
+//! together with the frame it was handled on:
+//!
+//! ```json
+//! {"seed":42,"width":80,"height":24,"events":[{"frame":12,"ms":1204,"resize":[100,30]},{"frame":40,"ms":4012,"key":"q"}]}
+//! ```
+
+use anyhow::Context;
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use std::collections::VecDeque;
+use std::path::Path;
+use std::time::Duration;
+
+pub struct LoggedEvent {
+  pub frame: u64,
+  pub millis: u64,
+  pub event: Event,
+}
+
+pub struct EventLog {
+  pub seed: u64,
+  pub width: u16,
+  pub height: u16,
+  pub events: Vec<LoggedEvent>,
+}
+
+impl EventLog {
+  pub fn new(seed: u64, width: u16, height: u16) -> Self {
+    Self {
+      seed,
+      width,
+      height,
+      events: Vec::new(),
+    }
+  }
+
+  /// Records `event` if it is one that influences the simulation.
+  pub fn push(&mut self, frame: u64, elapsed: Duration, event: &Event) {
+    let recordable = match event {
+      Event::Resize(..) | Event::FocusGained | Event::FocusLost => true,
+      Event::Key(key) => key_name(key).is_some(),
+      _ => false,
+    };
+
+    if recordable {
+      self.events.push(LoggedEvent {
+        frame,
+        millis: elapsed.as_millis() as u64,
+        event: event.clone(),
+      });
+    }
+  }
+
+  pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+    let events = self
+      .events
+      .iter()
+      .map(|e| {
+        let payload = match &e.event {
+          Event::Resize(w, h) => format!("\"resize\":[{},{}]", w, h),
+          Event::Key(key) => format!(
+            "\"key\":{}",
+            json_string(&key_name(key).unwrap_or_default())
+          ),
+          Event::FocusGained => "\"focus\":\"gained\"".to_owned(),
+          Event::FocusLost => "\"focus\":\"lost\"".to_owned(),
+          _ => unreachable!("only resize, focus and key events are recorded"),
+        };
+        format!("{{\"frame\":{},\"ms\":{},{}}}", e.frame, e.millis, payload)
+      })
+      .collect::<Vec<_>>()
+      .join(",");
+
+    let json = format!(
+      "{{\"seed\":{},\"width\":{},\"height\":{},\"events\":[{}]}}\n",
+      self.seed, self.width, self.height, events
+    );
+
+    std::fs::write(path, json)
+      .with_context(|| format!("failed to write event log to {}", path.display()))
+  }
+
+  pub fn load(path: &Path) -> anyhow::Result<Self> {
+    let src = std::fs::read_to_string(path)
+      .with_context(|| format!("failed to read event log from {}", path.display()))?;
+
+    Self::parse(&src).with_context(|| format!("malformed event log {}", path.display()))
+  }
+
+  fn parse(src: &str) -> anyhow::Result<Self> {
+    let root = Parser::new(src).document()?;
+
+    let mut log = Self::new(
+      root.field("seed")?.number()?,
+      root.field("width")?.number()? as u16,
+      root.field("height")?.number()? as u16,
+    );
+
+    for entry in root.field("events")?.array()? {
+      let event = if let Ok(size) = entry.field("resize") {
+        match size.array()? {
+          [w, h] => Event::Resize(w.number()? as u16, h.number()? as u16),
+          _ => anyhow::bail!("`resize` expects [width, height]"),
+        }
+      } else if let Ok(focus) = entry.field("focus") {
+        match focus.string()? {
+          "gained" => Event::FocusGained,
+          "lost" => Event::FocusLost,
+          other => anyhow::bail!("unknown focus state `{}`", other),
+        }
+      } else {
+        Event::Key(parse_key(entry.field("key")?.string()?)?)
+      };
+
+      log.events.push(LoggedEvent {
+        frame: entry.field("frame")?.number()?,
+        millis: entry.field("ms")?.number()?,
+        event,
+      });
+    }
+
+    Ok(log)
+  }
+}
+
+/// Feeds the events of a recorded log back on the frames they were recorded at.
+pub struct Replay {
+  events: VecDeque<LoggedEvent>,
+}
+
+impl Replay {
+  pub fn new(log: EventLog) -> Self {
+    Self {
+      events: log.events.into(),
+    }
+  }
+
+  /// Events recorded for `frame` (or earlier ones that were not yet taken).
+  pub fn take(&mut self, frame: u64) -> Vec<Event> {
+    let mut events = Vec::new();
+    while self.events.front().is_some_and(|e| e.frame <= frame) {
+      events.extend(self.events.pop_front().map(|e| e.event));
+    }
+    events
+  }
+}
+
+/// Stable textual name of a key press, e.g. `q`, `ctrl+c`, `esc`, `space`.
+pub fn key_name(key: &KeyEvent) -> Option<String> {
+  if key.kind != KeyEventKind::Press {
+    return None;
+  }
+
+  let name = match key.code {
+    KeyCode::Char(' ') => "space".to_owned(),
+    KeyCode::Char(c) => c.to_string(),
+    KeyCode::Esc => "esc".to_owned(),
+    KeyCode::Enter => "enter".to_owned(),
+    KeyCode::Tab => "tab".to_owned(),
+    KeyCode::Backspace => "backspace".to_owned(),
+    KeyCode::Up => "up".to_owned(),
+    KeyCode::Down => "down".to_owned(),
+    KeyCode::Left => "left".to_owned(),
+    KeyCode::Right => "right".to_owned(),
+    _ => return None,
+  };
+
+  if key.modifiers.contains(KeyModifiers::CONTROL) {
+    Some(format!("ctrl+{}", name))
+  } else {
+    Some(name)
+  }
+}
+
+/// Inverse of [`key_name`].
+pub fn parse_key(name: &str) -> anyhow::Result<KeyEvent> {
+  let (modifiers, name) = match name.strip_prefix("ctrl+") {
+    Some(name) => (KeyModifiers::CONTROL, name),
+    None => (KeyModifiers::NONE, name),
+  };
+
+  let code = match name {
+    "space" => KeyCode::Char(' '),
+    "esc" => KeyCode::Esc,
+    "enter" => KeyCode::Enter,
+    "tab" => KeyCode::Tab,
+    "backspace" => KeyCode::Backspace,
+    "up" => KeyCode::Up,
+    "down" => KeyCode::Down,
+    "left" => KeyCode::Left,
+    "right" => KeyCode::Right,
+    _ => {
+      let mut chars = name.chars();
+      match (chars.next(), chars.next()) {
+        (Some(c), None) => KeyCode::Char(c),
+        _ => anyhow::bail!("unknown key `{}`", name),
+      }
+    }
+  };
+
+  Ok(KeyEvent::new(code, modifiers))
+}
+
+/// `s` as a JSON string literal. Quotes, backslashes and control characters are
+/// escaped, everything else is written as is.
+fn json_string(s: &str) -> String {
+  let mut out = String::with_capacity(s.len() + 2);
+  out.push('"');
+  for c in s.chars() {
+    match c {
+      '"' => out.push_str("\\\""),
+      '\\' => out.push_str("\\\\"),
+      '\n' => out.push_str("\\n"),
+      '\t' => out.push_str("\\t"),
+      '\r' => out.push_str("\\r"),
+      c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+      c => out.push(c),
+    }
+  }
+  out.push('"');
+  out
+}
+
+/// The subset of JSON the event log uses: objects, arrays, strings and unsigned integers.
+enum Json {
+  Number(u64),
+  String(String),
+  Array(Vec<Json>),
+  Object(Vec<(String, Json)>),
+}
+
+impl Json {
+  fn field(&self, name: &str) -> anyhow::Result<&Json> {
+    match self {
+      Json::Object(fields) => fields
+        .iter()
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value)
+        .with_context(|| format!("missing field `{}`", name)),
+      _ => anyhow::bail!("expected an object"),
+    }
+  }
+
+  fn number(&self) -> anyhow::Result<u64> {
+    match self {
+      Json::Number(n) => Ok(*n),
+      _ => anyhow::bail!("expected a number"),
+    }
+  }
+
+  fn string(&self) -> anyhow::Result<&str> {
+    match self {
+      Json::String(s) => Ok(s),
+      _ => anyhow::bail!("expected a string"),
+    }
+  }
+
+  fn array(&self) -> anyhow::Result<&[Json]> {
+    match self {
+      Json::Array(items) => Ok(items),
+      _ => anyhow::bail!("expected an array"),
+    }
+  }
+}
+
+struct Parser<'a> {
+  src: &'a str,
+  pos: usize,
+}
+
+impl<'a> Parser<'a> {
+  fn new(src: &'a str) -> Self {
+    Self { src, pos: 0 }
+  }
+
+  fn document(mut self) -> anyhow::Result<Json> {
+    let value = self.value()?;
+    self.skip_whitespace();
+    if self.pos != self.src.len() {
+      anyhow::bail!("trailing characters at offset {}", self.pos);
+    }
+    Ok(value)
+  }
+
+  fn skip_whitespace(&mut self) {
+    let rest = &self.src[self.pos..];
+    self.pos += rest.len() - rest.trim_start().len();
+  }
+
+  fn peek(&mut self) -> Option<char> {
+    self.skip_whitespace();
+    self.src[self.pos..].chars().next()
+  }
+
+  fn expect(&mut self, c: char) -> anyhow::Result<()> {
+    if self.peek() != Some(c) {
+      anyhow::bail!("expected `{}` at offset {}", c, self.pos);
+    }
+    self.pos += c.len_utf8();
+    Ok(())
+  }
+
+  fn value(&mut self) -> anyhow::Result<Json> {
+    match self.peek() {
+      Some('{') => self.object(),
+      Some('[') => self.array(),
+      Some('"') => self.string().map(Json::String),
+      Some(c) if c.is_ascii_digit() => {
+        let digits = self.src[self.pos..]
+          .find(|c: char| !c.is_ascii_digit())
+          .unwrap_or(self.src.len() - self.pos);
+        let n = self.src[self.pos..self.pos + digits].parse()?;
+        self.pos += digits;
+        Ok(Json::Number(n))
+      }
+      _ => anyhow::bail!("unexpected input at offset {}", self.pos),
+    }
+  }
+
+  fn object(&mut self) -> anyhow::Result<Json> {
+    self.expect('{')?;
+    let mut fields = Vec::new();
+    if self.peek() == Some('}') {
+      self.pos += 1;
+      return Ok(Json::Object(fields));
+    }
+
+    loop {
+      let key = self.string()?;
+      self.expect(':')?;
+      fields.push((key, self.value()?));
+
+      match self.peek() {
+        Some(',') => self.pos += 1,
+        _ => break,
+      }
+    }
+
+    self.expect('}')?;
+    Ok(Json::Object(fields))
+  }
+
+  fn array(&mut self) -> anyhow::Result<Json> {
+    self.expect('[')?;
+    let mut items = Vec::new();
+    if self.peek() == Some(']') {
+      self.pos += 1;
+      return Ok(Json::Array(items));
+    }
+
+    loop {
+      items.push(self.value()?);
+
+      match self.peek() {
+        Some(',') => self.pos += 1,
+        _ => break,
+      }
+    }
+
+    self.expect(']')?;
+    Ok(Json::Array(items))
+  }
+
+  fn string(&mut self) -> anyhow::Result<String> {
+    self.expect('"')?;
+    let mut out = String::new();
+    let mut chars = self.src[self.pos..].char_indices();
+
+    while let Some((i, c)) = chars.next() {
+      match c {
+        '"' => {
+          self.pos += i + 1;
+          return Ok(out);
+        }
+        '\\' => match chars.next() {
+          Some((_, 'n')) => out.push('\n'),
+          Some((_, 't')) => out.push('\t'),
+          Some((_, 'r')) => out.push('\r'),
+          Some((_, 'b')) => out.push('\u{8}'),
+          Some((_, 'f')) => out.push('\u{c}'),
+          Some((_, 'u')) => {
+            let hex: String = chars.by_ref().take(4).map(|(_, c)| c).collect();
+            let code = u32::from_str_radix(&hex, 16)?;
+            out.push(char::from_u32(code).context("invalid unicode escape")?);
+          }
+          Some((_, c)) => out.push(c),
+          None => break,
+        },
+        c => out.push(c),
+      }
+    }
+
+    anyhow::bail!("unterminated string")
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// `log` written to a file and read back.
+  fn round_trip(log: &EventLog, name: &str) -> (String, EventLog) {
+    let path = std::env::temp_dir().join(format!("rmatrix-{}-{}.json", name, std::process::id()));
+    log.save(&path).unwrap();
+    let saved = std::fs::read_to_string(&path).unwrap();
+    let loaded = EventLog::load(&path);
+    std::fs::remove_file(&path).unwrap();
+    (saved, loaded.unwrap())
+  }
+
+  #[test]
+  fn saved_logs_load_back() {
+    let keys = [
+      KeyEvent::new(KeyCode::Char('"'), KeyModifiers::NONE),
+      KeyEvent::new(KeyCode::Char('\\'), KeyModifiers::NONE),
+      KeyEvent::new(KeyCode::Char('\u{1b}'), KeyModifiers::NONE),
+      KeyEvent::new(KeyCode::Char('\u{7f}'), KeyModifiers::CONTROL),
+      KeyEvent::new(KeyCode::Char('\n'), KeyModifiers::NONE),
+      KeyEvent::new(KeyCode::Char('\r'), KeyModifiers::NONE),
+      KeyEvent::new(KeyCode::Char('é'), KeyModifiers::NONE),
+      KeyEvent::new(KeyCode::Char('日'), KeyModifiers::CONTROL),
+      KeyEvent::new(KeyCode::Char('🦀'), KeyModifiers::NONE),
+      KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE),
+      KeyEvent::new(KeyCode::Char(' '), KeyModifiers::NONE),
+    ];
+    let mut log = EventLog::new(42, 80, 24);
+    let mut events: Vec<Event> = keys.into_iter().map(Event::Key).collect();
+    events.extend([Event::Resize(100, 30), Event::FocusLost, Event::FocusGained]);
+    for (frame, event) in events.iter().enumerate() {
+      log.push(
+        frame as u64,
+        Duration::from_millis(frame as u64 * 100),
+        event,
+      );
+    }
+
+    let (saved, loaded) = round_trip(&log, "replay-keys");
+    // control characters only appear escaped
+    assert!(!saved.trim_end().chars().any(char::is_control), "{}", saved);
+    assert!(saved.contains(r#""key":"\u001b""#), "{}", saved);
+    assert_eq!((loaded.seed, loaded.width, loaded.height), (42, 80, 24));
+    let loaded: Vec<(u64, u64, Event)> = loaded
+      .events
+      .into_iter()
+      .map(|e| (e.frame, e.millis, e.event))
+      .collect();
+    let expected: Vec<(u64, u64, Event)> = log
+      .events
+      .into_iter()
+      .map(|e| (e.frame, e.millis, e.event))
+      .collect();
+    assert_eq!(loaded, expected);
+  }
+
+  #[test]
+  fn strings_round_trip_through_json() {
+    for s in [
+      "",
+      "q",
+      "\"",
+      "\\",
+      "a\"b\\c",
+      "\u{0}\u{1f}\u{7f}\u{9f}",
+      "\t\n\r",
+      "ü日🦀",
+    ] {
+      let json = json_string(s);
+      assert_eq!(Parser::new(&json).document().unwrap().string().unwrap(), s);
+    }
+  }
+}
@@ -0,0 +1,78 @@
+//! Text resolving out of the rain for [`Config::reveal`](crate::Config::reveal).
+
+use crate::drop::mix;
+use crate::glyph::Glyph;
+
+/// Frames every char shows only random glyphs before the first one may start resolving.
+const SCRAMBLE_FRAMES: u64 = 40;
+
+/// Frames a char flickers between random glyphs and itself before it locks.
+const RESOLVE_FRAMES: u64 = 20;
+
+/// Frames over which the chars lock, one after another in a random order.
+const LOCK_SPREAD: u64 = 80;
+
+/// Where a char of a [`Reveal`] is in its transition, by frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RevealState {
+  /// Random glyphs.
+  Scrambling,
+  /// Random glyphs with the right char showing every other frame.
+  Resolving,
+  /// The right char for good.
+  Locked,
+}
+
+/// A line of text the rain resolves into, char by char, from the start of the run.
+#[derive(Clone, Debug)]
+pub struct Reveal {
+  chars: Vec<char>,
+}
+
+impl Reveal {
+  /// Fails on empty text and on chars that aren't a single column wide.
+  pub fn new(text: &str) -> anyhow::Result<Self> {
+    if text.trim().is_empty() {
+      anyhow::bail!("reveal text is empty");
+    }
+    if let Some(c) = text.chars().find(|&c| Glyph::from(c).width() != 1) {
+      anyhow::bail!("{:?} isn't a single column wide", c);
+    }
+
+    Ok(Self {
+      chars: text.chars().collect(),
+    })
+  }
+
+  pub fn text(&self) -> String {
+    self.chars.iter().collect()
+  }
+
+  pub fn width(&self) -> u16 {
+    self.chars.len().min(u16::MAX as usize) as u16
+  }
+
+  /// The char at `col`, `None` past the end and for spaces, which the rain falls through.
+  pub fn char_at(&self, col: i32) -> Option<char> {
+    let c = *self.chars.get(usize::try_from(col).ok()?)?;
+    (c != ' ').then_some(c)
+  }
+
+  /// State of the char at `col` on `frame`. Every char locks on its own frame,
+  /// picked from `seed` so a replay resolves in the same order.
+  pub fn state(&self, col: usize, frame: u64, seed: u64) -> RevealState {
+    let locks_at = SCRAMBLE_FRAMES + RESOLVE_FRAMES + mix(seed ^ mix(col as u64)) % LOCK_SPREAD;
+    if frame >= locks_at {
+      RevealState::Locked
+    } else if frame + RESOLVE_FRAMES >= locks_at {
+      RevealState::Resolving
+    } else {
+      RevealState::Scrambling
+    }
+  }
+
+  /// Whether every char is locked on `frame`.
+  pub fn is_done(&self, frame: u64) -> bool {
+    frame >= SCRAMBLE_FRAMES + RESOLVE_FRAMES + LOCK_SPREAD
+  }
+}
@@ -0,0 +1,54 @@
+//! Shader-like drop colors, a function of the cell and the time.
+//!
+//! A shader is called for every visible drop cell each frame, so it should stay
+//! cheap: a handful of float operations per call is fine, allocating is not.
+//! The result is still ramped down toward the tail, dimmed, tempered and so on
+//! like any other drop color.
+//!
+//! ```ignore
+//! use rmatrix::RainStyle;
+//! use std::sync::Arc;
+//!
+//! // vertical stripes that scroll sideways
+//! config.style = RainStyle::Shader(Arc::new(|x, _y, _width, _height, t| {
+//!   if (x as f32 + t * 4.0) as u32 % 8 < 4 { Color::Green } else { Color::DarkGreen }
+//! }));
+//! ```
+
+use crate::crossterm_ext::{hsv_to_rgb, ColorExt};
+use crossterm::style::Color;
+use std::sync::Arc;
+
+/// Color of the cell at column `x`, row `y` of a `width` x `height` screen,
+/// `t` seconds into the run.
+pub type Shader = Arc<dyn Fn(u16, u16, u16, u16, f32) -> Color + Send + Sync>;
+
+/// Shader for one of the built-in names, `plasma` or `radial`.
+pub fn builtin(name: &str) -> Option<Shader> {
+  match name {
+    "plasma" => Some(Arc::new(plasma)),
+    "radial" => Some(Arc::new(radial)),
+    _ => None,
+  }
+}
+
+fn hue(degrees: f32) -> Color {
+  let (r, g, b) = hsv_to_rgb(degrees.rem_euclid(360.0), 1.0, 1.0);
+  Color::rgb(r, g, b)
+}
+
+/// Drifting blobs of color from overlapping sine waves.
+fn plasma(x: u16, y: u16, _width: u16, _height: u16, t: f32) -> Color {
+  let (x, y) = (x as f32, y as f32);
+  let v = (x / 8.0 + t).sin() + (y / 4.0 - t * 0.7).sin() + ((x + y * 2.0) / 12.0 + t * 0.5).sin();
+  // `v` is within -3..3
+  hue((v + 3.0) * 60.0)
+}
+
+/// Rings of color expanding from the center of the screen.
+fn radial(x: u16, y: u16, width: u16, height: u16, t: f32) -> Color {
+  // cells are about twice as tall as they are wide
+  let dx = x as f32 - width as f32 / 2.0;
+  let dy = (y as f32 - height as f32 / 2.0) * 2.0;
+  hue((dx * dx + dy * dy).sqrt() * 8.0 - t * 90.0)
+}
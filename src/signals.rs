@@ -0,0 +1,60 @@
+//! Shutdown requests from outside the terminal, e.g. a screensaver daemon sending
+//! SIGTERM, or the console window being closed on Windows. The main loops check
+//! [`shutdown_requested`] every frame and return, so the terminal is restored like
+//! on a quit key.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, LazyLock};
+#[cfg(windows)]
+use winapi::shared::minwindef::{BOOL, DWORD, FALSE, TRUE};
+
+static SHUTDOWN: LazyLock<Arc<AtomicBool>> = LazyLock::new(Arc::default);
+
+/// Turns SIGTERM, SIGHUP and SIGINT, or on Windows the console's control events, into
+/// a shutdown request. Ctrl-C in the raw mode terminal still arrives as a key.
+pub fn install() -> anyhow::Result<()> {
+  #[cfg(unix)]
+  for signal in [
+    signal_hook::consts::SIGTERM,
+    signal_hook::consts::SIGHUP,
+    signal_hook::consts::SIGINT,
+  ] {
+    signal_hook::flag::register(signal, Arc::clone(&SHUTDOWN))?;
+  }
+
+  #[cfg(windows)]
+  {
+    // SAFETY: the handler is a plain function that only touches an atomic and sleeps
+    let added = unsafe { winapi::um::consoleapi::SetConsoleCtrlHandler(Some(console_ctrl), TRUE) };
+    if added == FALSE {
+      return Err(std::io::Error::last_os_error().into());
+    }
+  }
+
+  Ok(())
+}
+
+/// Console control handler, run by Windows on a thread of its own.
+///
+/// Ctrl-C and Ctrl-Break only ask for a shutdown. On a closed window, logoff or
+/// shutdown the process is killed once the handler returns, so it waits for the
+/// main loop to restore the terminal and exit first, within the few seconds
+/// Windows allows.
+#[cfg(windows)]
+unsafe extern "system" fn console_ctrl(event: DWORD) -> BOOL {
+  use winapi::um::wincon::{CTRL_CLOSE_EVENT, CTRL_LOGOFF_EVENT, CTRL_SHUTDOWN_EVENT};
+
+  SHUTDOWN.store(true, Ordering::Relaxed);
+  if matches!(
+    event,
+    CTRL_CLOSE_EVENT | CTRL_LOGOFF_EVENT | CTRL_SHUTDOWN_EVENT
+  ) {
+    // the process exits from under the sleep as soon as the main loop is done
+    std::thread::sleep(std::time::Duration::from_secs(4));
+  }
+  TRUE
+}
+
+pub fn shutdown_requested() -> bool {
+  SHUTDOWN.load(Ordering::Relaxed)
+}
@@ -0,0 +1,554 @@
+//! Where the glyphs of the drops come from.
+//!
+//! Every drawn cell asks the configured [`GlyphSource`] for its glyph. The rng it
+//! is handed is seeded from the drop and the cell, so the same cell of the same
+//! drop gets the same glyph each frame and runs with a fixed seed are reproducible.
+//!
+//! A custom source only has to pick a glyph, and report its widest one if that
+//! is wider than a single column:
+//!
+//! ```ignore
+//! use rmatrix::glyph::Glyph;
+//! use rmatrix::source::GlyphSource;
+//! use rand::{Rng, RngCore};
+//! use std::sync::Arc;
+//!
+//! /// Binary rain.
+//! struct Bits;
+//!
+//! impl GlyphSource for Bits {
+//!   fn glyph(&self, _x: u16, _y: u16, _part: usize, rng: &mut dyn RngCore) -> Glyph {
+//!     Glyph::from(if rng.gen_bool(0.5) { '1' } else { '0' })
+//!   }
+//! }
+//!
+//! config.glyphs = Arc::new(Bits);
+//! ```
+
+use crate::drop::mix;
+use crate::glyph::{get_all_unicode_chars, Charset, Glyph};
+use anyhow::Context;
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::{Rng, RngCore};
+use std::fmt::{Display, Formatter};
+use std::io::Read;
+use std::str::FromStr;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Picks the glyph of a drop cell.
+pub trait GlyphSource: Send + Sync {
+  /// Glyph for the cell at column `x`, row `y` that is part `part` of its drop,
+  /// counted from the tail.
+  fn glyph(&self, x: u16, y: u16, part: usize, rng: &mut dyn RngCore) -> Glyph;
+
+  /// Like [`GlyphSource::glyph`], for a cell of the drop salted `drop`, which stays
+  /// the same for the drop's whole life. Sources that spell something along a drop
+  /// key it on this, by default the drop doesn't matter.
+  fn drop_glyph(&self, drop: u64, x: u16, y: u16, part: usize, rng: &mut dyn RngCore) -> Glyph {
+    let _ = drop;
+    self.glyph(x, y, part, rng)
+  }
+
+  /// Widest glyph the source can return, drops reserve this many columns.
+  fn width(&self) -> u8 {
+    1
+  }
+
+  /// Every glyph the source can return, to preview them. Empty when the source
+  /// can't list them.
+  fn glyphs(&self) -> Vec<Glyph> {
+    Vec::new()
+  }
+}
+
+/// Uniformly random glyphs from a charset, the default.
+pub struct Random {
+  charset: Charset,
+}
+
+impl Random {
+  pub fn new(charset: Charset) -> Self {
+    Self { charset }
+  }
+}
+
+impl GlyphSource for Random {
+  fn glyph(&self, _x: u16, _y: u16, _part: usize, rng: &mut dyn RngCore) -> Glyph {
+    self.charset.get(rng.next_u64() as usize).clone()
+  }
+
+  fn width(&self) -> u8 {
+    self.charset.width()
+  }
+
+  fn glyphs(&self) -> Vec<Glyph> {
+    self.charset.glyphs().to_vec()
+  }
+}
+
+/// Glyphs of the film's code: half-width katakana with digits and a few symbols.
+const AUTHENTIC_EXTRA: &str = "0123456789Z:.\"=*+-<>¦|";
+
+/// One in this many authentic glyphs is shown mirrored, where a mirrored form exists.
+const MIRROR_ODDS: u32 = 6;
+
+/// Mirrored forms of the non-katakana glyphs, katakana have no mirrored code points.
+const MIRRORED: &[(char, char)] = &[('2', 'Ƨ'), ('3', 'Ɛ'), ('Z', 'Ƨ'), ('<', '>'), ('>', '<')];
+
+/// The look of the original Matrix code: half-width katakana, digits and symbols,
+/// some of them occasionally flipped horizontally.
+pub struct Authentic {
+  charset: Charset,
+}
+
+impl Default for Authentic {
+  fn default() -> Self {
+    let chars: Vec<char> = ('\u{FF66}'..='\u{FF9D}')
+      .chain(AUTHENTIC_EXTRA.chars())
+      .collect();
+
+    Self {
+      charset: Charset::from_chars(&chars),
+    }
+  }
+}
+
+impl GlyphSource for Authentic {
+  fn glyph(&self, _x: u16, _y: u16, _part: usize, rng: &mut dyn RngCore) -> Glyph {
+    let glyph = self.charset.get(rng.next_u64() as usize);
+    if rng.gen_ratio(1, MIRROR_ODDS) {
+      let mirrored = MIRRORED
+        .iter()
+        .find(|&&(c, _)| *glyph == Glyph::from(c))
+        .map(|&(_, m)| Glyph::from(m));
+      if let Some(mirrored) = mirrored {
+        return mirrored;
+      }
+    }
+
+    glyph.clone()
+  }
+
+  fn width(&self) -> u8 {
+    self.charset.width()
+  }
+
+  fn glyphs(&self) -> Vec<Glyph> {
+    let mirrored = MIRRORED.iter().map(|&(_, m)| Glyph::from(m));
+    self
+      .charset
+      .glyphs()
+      .iter()
+      .cloned()
+      .chain(mirrored)
+      .collect()
+  }
+}
+
+/// One in this many connector cells branches off to a side.
+const BRANCH_ODDS: u32 = 12;
+
+/// Box-drawing lines that join into vertical traces with the occasional branch,
+/// for a circuit-diagram look.
+pub struct Connectors;
+
+impl GlyphSource for Connectors {
+  fn glyph(&self, _x: u16, _y: u16, _part: usize, rng: &mut dyn RngCore) -> Glyph {
+    let glyph = if !rng.gen_ratio(1, BRANCH_ODDS) {
+      '│'
+    } else if rng.gen_bool(0.5) {
+      '├'
+    } else {
+      '┤'
+    };
+
+    Glyph::from(glyph)
+  }
+
+  fn glyphs(&self) -> Vec<Glyph> {
+    ['│', '├', '┤'].into_iter().map(Glyph::from).collect()
+  }
+}
+
+/// What a [`Message`] shows in the cells of a drop that is longer than the text.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OnEof {
+  /// Starts the text over.
+  #[default]
+  Loop,
+  /// Random glyphs, like the default source.
+  Random,
+  /// Nothing, the cells are blank.
+  Stop,
+}
+
+impl Display for OnEof {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    f.write_str(match self {
+      OnEof::Loop => "loop",
+      OnEof::Random => "random",
+      OnEof::Stop => "stop",
+    })
+  }
+}
+
+impl FromStr for OnEof {
+  type Err = anyhow::Error;
+
+  fn from_str(s: &str) -> anyhow::Result<Self> {
+    match s {
+      "loop" => Ok(OnEof::Loop),
+      "random" => Ok(OnEof::Random),
+      "stop" => Ok(OnEof::Stop),
+      _ => anyhow::bail!("expected `loop`, `random` or `stop`"),
+    }
+  }
+}
+
+/// Spells a message along every drop, from the tail to the head.
+pub struct Message {
+  text: Charset,
+  on_eof: OnEof,
+  /// Glyphs past the end of the text with [`OnEof::Random`].
+  random: Random,
+}
+
+impl Message {
+  pub fn new(text: &str) -> anyhow::Result<Self> {
+    Ok(Self {
+      text: Charset::parse(text)?,
+      on_eof: OnEof::Loop,
+      random: Random::new(Charset::from_chars(get_all_unicode_chars())),
+    })
+  }
+
+  /// Sets what drops longer than the text show past its end, it loops by default.
+  pub fn on_eof(mut self, on_eof: OnEof) -> Self {
+    self.on_eof = on_eof;
+    self
+  }
+
+  /// Message made of everything `reader` yields, runs of whitespace become one space.
+  pub fn read(mut reader: impl Read) -> anyhow::Result<Self> {
+    let mut text = String::new();
+    reader
+      .read_to_string(&mut text)
+      .context("failed to read the message")?;
+
+    Self::new(&text.split_whitespace().collect::<Vec<_>>().join(" "))
+  }
+}
+
+impl GlyphSource for Message {
+  fn glyph(&self, x: u16, y: u16, part: usize, rng: &mut dyn RngCore) -> Glyph {
+    match self.on_eof {
+      _ if part < self.text.len() => self.text.get(part).clone(),
+      OnEof::Loop => self.text.get(part).clone(),
+      OnEof::Random => self.random.glyph(x, y, part, rng),
+      OnEof::Stop => Glyph::new(&" ".repeat(self.text.width() as usize)),
+    }
+  }
+
+  fn width(&self) -> u8 {
+    self.text.width()
+  }
+
+  fn glyphs(&self) -> Vec<Glyph> {
+    let mut glyphs = self.text.glyphs().to_vec();
+    if self.on_eof == OnEof::Random {
+      glyphs.extend(self.random.glyphs());
+    }
+    glyphs
+  }
+}
+
+/// Word list of [`Words::default`].
+const BUILTIN_WORDS: &[&str] = &[
+  "wake", "up", "neo", "the", "matrix", "has", "you", "follow", "white", "rabbit", "knock",
+  "there", "is", "no", "spoon", "red", "pill", "blue", "choice", "system", "control", "free",
+  "your", "mind", "agent", "smith", "oracle", "zion", "exit", "signal", "trace", "program",
+  "operator", "dream", "real", "world", "code", "rain",
+];
+
+/// Random words written down a drop one after another, a space in between. Every
+/// drop spells its own words, placed by screen row so they stay put while the drop
+/// slides over them, and the next drop in the column picks new ones. A drop shorter
+/// than a word shows just the part of the word it covers.
+pub struct Words {
+  words: Vec<Box<[Glyph]>>,
+  width: u8,
+}
+
+impl Default for Words {
+  fn default() -> Self {
+    Self::new(BUILTIN_WORDS).expect("the built-in words are valid")
+  }
+}
+
+impl Words {
+  pub fn new(words: &[impl AsRef<str>]) -> anyhow::Result<Self> {
+    let mut list = Vec::with_capacity(words.len());
+
+    for text in words {
+      let word: Box<[Glyph]> = text.as_ref().graphemes(true).map(Glyph::new).collect();
+      if word.iter().any(|glyph| glyph.width() == 0) {
+        anyhow::bail!("word {:?} contains a zero-width sequence", text.as_ref());
+      }
+      if !word.is_empty() {
+        list.push(word);
+      }
+    }
+
+    if list.is_empty() {
+      anyhow::bail!("word list is empty");
+    }
+
+    let width = list
+      .iter()
+      .flat_map(|word| word.iter().map(Glyph::width))
+      .max()
+      .unwrap_or(1);
+    Ok(Self { words: list, width })
+  }
+
+  /// Words separated by whitespace in everything `reader` yields, e.g. one word per line.
+  pub fn read(mut reader: impl Read) -> anyhow::Result<Self> {
+    let mut text = String::new();
+    reader
+      .read_to_string(&mut text)
+      .context("failed to read the word list")?;
+
+    Self::new(&text.split_whitespace().collect::<Vec<_>>())
+  }
+}
+
+impl GlyphSource for Words {
+  /// Words of the column `x` rather than of a drop, see [`GlyphSource::drop_glyph`].
+  fn glyph(&self, x: u16, y: u16, part: usize, rng: &mut dyn RngCore) -> Glyph {
+    self.drop_glyph(x as u64, x, y, part, rng)
+  }
+
+  fn drop_glyph(&self, drop: u64, _x: u16, y: u16, _part: usize, _rng: &mut dyn RngCore) -> Glyph {
+    let y = y as usize;
+    let mut start = 0;
+
+    // the drop's text is the same every frame, a hash of the drop and the word index,
+    // so every drop spells its own words and the next one in the column picks new ones
+    for i in 0.. {
+      let pick = mix(mix(drop) ^ i) as usize % self.words.len();
+      let word = &self.words[pick];
+      if y < start + word.len() {
+        return word[y - start].clone();
+      }
+      if y == start + word.len() {
+        return Glyph::from(' ');
+      }
+      start += word.len() + 1;
+    }
+
+    unreachable!("every row is covered by a word or the space after it")
+  }
+
+  fn width(&self) -> u8 {
+    self.width
+  }
+
+  fn glyphs(&self) -> Vec<Glyph> {
+    self.words.iter().flatten().cloned().collect()
+  }
+}
+
+/// Random glyphs where some show up more often than others.
+pub struct Weighted {
+  glyphs: Vec<(Glyph, u32)>,
+  total: u32,
+}
+
+impl Weighted {
+  /// Parses `glyph=weight` pairs separated by commas, e.g. `ア=3,0=1,1=1`.
+  pub fn parse(s: &str) -> anyhow::Result<Self> {
+    let mut glyphs = Vec::new();
+
+    for pair in s.split(',') {
+      let (glyph, weight) = pair
+        .rsplit_once('=')
+        .with_context(|| format!("expected `glyph=weight`, got {:?}", pair))?;
+      let glyph = Glyph::new(glyph);
+      if glyph.width() == 0 {
+        anyhow::bail!("weighted glyph {:?} is empty or zero-width", pair);
+      }
+      glyphs.push((glyph, weight.parse()?));
+    }
+
+    let total = glyphs
+      .iter()
+      .try_fold(0_u32, |total, (_, weight)| total.checked_add(*weight))
+      .context("weights add up to more than 4294967295")?;
+    if total == 0 {
+      anyhow::bail!("weights add up to zero");
+    }
+
+    Ok(Self { glyphs, total })
+  }
+}
+
+impl GlyphSource for Weighted {
+  fn glyph(&self, _x: u16, _y: u16, _part: usize, rng: &mut dyn RngCore) -> Glyph {
+    let mut pick = rng.gen_range(0..self.total);
+    for (glyph, weight) in &self.glyphs {
+      if pick < *weight {
+        return glyph.clone();
+      }
+      pick -= weight;
+    }
+
+    unreachable!("pick is below the total weight")
+  }
+
+  fn width(&self) -> u8 {
+    self
+      .glyphs
+      .iter()
+      .map(|(glyph, _)| glyph.width())
+      .max()
+      .unwrap_or(1)
+  }
+
+  fn glyphs(&self) -> Vec<Glyph> {
+    self
+      .glyphs
+      .iter()
+      .filter(|(_, weight)| *weight > 0)
+      .map(|(glyph, _)| glyph.clone())
+      .collect()
+  }
+}
+
+/// Glyphs from several charsets mixed by weight, e.g. mostly katakana with some digits.
+///
+/// A charset is picked in proportion to its weight relative to the sum of all weights,
+/// then one of its glyphs uniformly. A glyph in several of the charsets shows up with
+/// their shares combined.
+pub struct Mixed {
+  charsets: Vec<Charset>,
+  /// Weight of every charset, a charset of weight 0 is never picked.
+  weights: Vec<f32>,
+  index: WeightedIndex<f32>,
+}
+
+impl Mixed {
+  /// Parses comma separated `<charset>:<weight>` entries, e.g. `katakana:0.7,digits:0.25`.
+  /// A charset is one of the [`Charset::named`] sets or else the glyphs themselves,
+  /// the weight defaults to 1.
+  pub fn parse(s: &str) -> anyhow::Result<Self> {
+    let mut charsets = Vec::new();
+    let mut weights = Vec::new();
+
+    for entry in s.split(',') {
+      let (name, weight) = match entry.rsplit_once(':') {
+        Some((name, weight)) => (name, weight.parse::<f32>()?),
+        None => (entry, 1.0),
+      };
+      if !weight.is_finite() || weight < 0.0 {
+        anyhow::bail!(
+          "weight of {:?} must be a finite number of at least 0",
+          entry
+        );
+      }
+
+      let charset = match Charset::named(name) {
+        Some(charset) => charset,
+        None => Charset::parse(name).with_context(|| format!("in {:?}", entry))?,
+      };
+      charsets.push(charset);
+      weights.push(weight);
+    }
+
+    if weights.iter().sum::<f32>() <= 0.0 {
+      anyhow::bail!("charset weights add up to zero");
+    }
+
+    Ok(Self {
+      charsets,
+      index: WeightedIndex::new(&weights)?,
+      weights,
+    })
+  }
+}
+
+impl GlyphSource for Mixed {
+  fn glyph(&self, _x: u16, _y: u16, _part: usize, rng: &mut dyn RngCore) -> Glyph {
+    let charset = &self.charsets[self.index.sample(rng)];
+    charset.get(rng.next_u64() as usize).clone()
+  }
+
+  fn width(&self) -> u8 {
+    self.charsets.iter().map(Charset::width).max().unwrap_or(1)
+  }
+
+  fn glyphs(&self) -> Vec<Glyph> {
+    self
+      .charsets
+      .iter()
+      .zip(&self.weights)
+      .filter(|(_, &weight)| weight > 0.0)
+      .flat_map(|(charset, _)| charset.glyphs().iter().cloned())
+      .collect()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use rand::rngs::SmallRng;
+  use rand::SeedableRng;
+
+  #[test]
+  fn weighted_rejects_zero_overflowing_and_malformed_weights() {
+    assert!(Weighted::parse("a=0,b=0").is_err());
+    assert!(Weighted::parse("a=4294967295,b=1").is_err());
+    assert!(Weighted::parse("a").is_err());
+    assert!(Weighted::parse("a=x").is_err());
+    assert!(Weighted::parse("a=-1").is_err());
+    assert!(Weighted::parse("=1").is_err());
+  }
+
+  #[test]
+  fn weighted_picks_only_glyphs_with_weight() {
+    let source = Weighted::parse("a=4294967294,b=1,c=0").unwrap();
+    let mut rng = SmallRng::seed_from_u64(1);
+    for _ in 0..1000 {
+      assert_ne!(source.glyph(0, 0, 0, &mut rng), Glyph::from('c'));
+    }
+    assert_eq!(source.glyphs(), vec![Glyph::from('a'), Glyph::from('b')]);
+  }
+
+  /// The text `drop` spells down rows `0..rows` of column 0.
+  fn spelled(words: &Words, drop: u64, rows: u16) -> String {
+    let mut rng = SmallRng::seed_from_u64(0);
+    (0..rows)
+      .map(|y| words.drop_glyph(drop, 0, y, 0, &mut rng).to_string())
+      .collect()
+  }
+
+  #[test]
+  fn every_drop_spells_its_own_words() {
+    let list = ["alpha", "beta", "gamma", "delta", "omega"];
+    let words = Words::new(&list).unwrap();
+
+    let texts: Vec<String> = (0..20).map(|drop| spelled(&words, drop, 40)).collect();
+    for text in &texts {
+      // whole words of the list, but for the last one the column cuts off
+      let mut spelled: Vec<&str> = text.split(' ').collect();
+      let cut = spelled.pop().unwrap();
+      assert!(spelled.iter().all(|word| list.contains(word)), "{:?}", text);
+      assert!(list.iter().any(|word| word.starts_with(cut)), "{:?}", text);
+    }
+    // the same drop keeps its text, another in the same column has different words
+    assert_eq!(texts[3], spelled(&words, 3, 40));
+    let mut distinct = texts.clone();
+    distinct.sort();
+    distinct.dedup();
+    assert!(distinct.len() > 15, "{:?}", texts);
+  }
+}
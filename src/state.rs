@@ -0,0 +1,152 @@
+//! The seed and frame count a run ended on, saved so the next launch can pick up
+//! where it left off. The file holds the two in the config file format:
+//!
+//! ```toml
+//! seed = 42
+//! frame = 12000
+//! ```
+
+use anyhow::Context;
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
+use rmatrix::{Config, Rain};
+use std::path::Path;
+
+/// Most frames a resumed rain is ticked through before it is drawn, enough for the
+/// slowest drops to cross a tall screen. Launches stay quick after any length of run.
+const MAX_REPLAY: u64 = 2000;
+
+pub struct State {
+  pub seed: u64,
+  pub frame: u64,
+}
+
+impl State {
+  /// The state saved at `path`, `None` when there is none yet or it can't be read,
+  /// the run then starts fresh.
+  pub fn load(path: &Path) -> Option<Self> {
+    let src = match std::fs::read_to_string(path) {
+      Ok(src) => src,
+      Err(e) if e.kind() == std::io::ErrorKind::NotFound => return None,
+      Err(e) => {
+        log::warn!("failed to read state from {}: {}", path.display(), e);
+        return None;
+      }
+    };
+
+    match Self::parse(&src) {
+      Ok(state) => Some(state),
+      Err(e) => {
+        log::warn!("ignoring malformed state file {}: {:#}", path.display(), e);
+        None
+      }
+    }
+  }
+
+  fn parse(src: &str) -> anyhow::Result<Self> {
+    let (mut seed, mut frame) = (None, None);
+
+    for line in src.lines().map(str::trim).filter(|line| !line.is_empty()) {
+      let (key, value) = line
+        .split_once('=')
+        .with_context(|| format!("expected `key = value`, got {:?}", line))?;
+      let value: u64 = value
+        .trim()
+        .parse()
+        .with_context(|| format!("invalid value for `{}`: {:?}", key.trim(), value.trim()))?;
+      match key.trim() {
+        "seed" => seed = Some(value),
+        "frame" => frame = Some(value),
+        key => anyhow::bail!("unknown key `{}`", key),
+      }
+    }
+
+    Ok(Self {
+      seed: seed.context("missing `seed`")?,
+      frame: frame.context("missing `frame`")?,
+    })
+  }
+
+  /// The rain of `config` on a `width` x `height` screen, carrying on from this state:
+  /// seeded from it and ticked headless up to the saved frame.
+  ///
+  /// Past [`MAX_REPLAY`] frames only the last that many are ticked, from a new seed
+  /// derived from both: the drops aren't the ones that stopped, but a settled rain
+  /// that can't be told apart from them. The frame count and the simulated time carry
+  /// on from the saved frame either way, so effects that follow them don't jump.
+  pub fn resume(&self, mut config: Config, width: u16, height: u16) -> Rain {
+    let (seed, from) = if self.frame > MAX_REPLAY {
+      let seed = SmallRng::seed_from_u64(self.seed ^ self.frame).gen();
+      (seed, self.frame - MAX_REPLAY)
+    } else {
+      (self.seed, 0)
+    };
+    config.seed = Some(seed);
+
+    let mut rain = Rain::new(config, width, height);
+    rain.skip_to(from);
+    for _ in from..self.frame {
+      rain.tick();
+    }
+    rain
+  }
+
+  pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+    std::fs::write(
+      path,
+      format!("seed = {}\nframe = {}\n", self.seed, self.frame),
+    )
+    .with_context(|| format!("failed to write state to {}", path.display()))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn config() -> Config {
+    let mut config = Config::default();
+    config.set("hue-cycle", "7s").unwrap();
+    config
+  }
+
+  /// The rain after `frames` frames without a break.
+  fn uninterrupted(seed: u64, frames: u64) -> Rain {
+    let mut config = config();
+    config.seed = Some(seed);
+    let mut rain = Rain::new(config, 20, 8);
+    for _ in 0..frames {
+      rain.tick();
+    }
+    rain
+  }
+
+  #[test]
+  fn short_runs_resume_exactly() {
+    let state = State {
+      seed: 42,
+      frame: 300,
+    };
+    let rain = state.resume(config(), 20, 8);
+    assert_eq!(rain.seed(), 42);
+    assert!(rain.render_frame() == uninterrupted(42, 300).render_frame());
+  }
+
+  #[test]
+  fn long_runs_resume_on_their_clock() {
+    let frame = MAX_REPLAY * 2 + 123;
+    let state = State { seed: 42, frame };
+    let rain = state.resume(config(), 20, 8);
+    let reference = uninterrupted(42, frame);
+
+    // a settled rain of its own, but at the same point of the hue cycle and time
+    assert_ne!(rain.seed(), 42);
+    assert_eq!(rain.frame(), frame);
+    assert_eq!(rain.elapsed(), reference.elapsed());
+    assert_ne!(reference.hue_shift(), 0.0);
+    assert_eq!(rain.hue_shift(), reference.hue_shift());
+
+    // the same saved state resumes the same rain
+    assert!(state.resume(config(), 20, 8).render_frame() == rain.render_frame());
+  }
+}
@@ -0,0 +1,58 @@
+use anyhow::Context;
+use rmatrix::Rain;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::time::Duration;
+
+const HEADER: &str = "frame,draw_ms,total_ms,drops,width,height";
+
+/// One CSV row per frame for graphing a run's performance elsewhere.
+/// Rows are appended to the file, so several runs can be compared in one.
+pub struct FrameStats {
+  out: BufWriter<File>,
+}
+
+impl FrameStats {
+  pub fn create(path: &Path) -> anyhow::Result<Self> {
+    let file = File::options()
+      .append(true)
+      .create(true)
+      .open(path)
+      .with_context(|| format!("failed to open {}", path.display()))?;
+    let empty = file.metadata()?.len() == 0;
+
+    let mut out = BufWriter::new(file);
+    if empty {
+      writeln!(out, "{}", HEADER)?;
+    }
+
+    Ok(Self { out })
+  }
+
+  /// Writes the row of the frame `rain` just finished.
+  pub fn frame(&mut self, rain: &Rain, draw: Duration, total: Duration) -> anyhow::Result<()> {
+    let (width, height) = rain.size();
+    writeln!(
+      self.out,
+      "{},{:.3},{:.3},{},{},{}",
+      rain.frame(),
+      draw.as_secs_f64() * 1000.0,
+      total.as_secs_f64() * 1000.0,
+      rain.drop_count(),
+      width,
+      height
+    )
+    .context("failed to write the frame timings")?;
+
+    Ok(())
+  }
+}
+
+impl Drop for FrameStats {
+  fn drop(&mut self) {
+    if let Err(e) = self.out.flush() {
+      log::warn!("failed to write the frame timings: {}", e);
+    }
+  }
+}
@@ -0,0 +1,146 @@
+use crossterm::cursor::{self, MoveTo};
+use crossterm::event::{DisableFocusChange, EnableFocusChange};
+use crossterm::execute;
+use crossterm::style::ResetColor;
+use crossterm::terminal::{
+  self, Clear, ClearType, DisableLineWrap, EnableLineWrap, EnterAlternateScreen,
+  LeaveAlternateScreen,
+};
+use std::fmt::{Display, Formatter};
+use std::io::{stderr, stdout, BufWriter, Stderr, Stdout, Write};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Whether the terminal is currently set up for drawing, so it is restored exactly once
+/// even when both the panic hook and the guard's `Drop` run.
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+static ALT_SCREEN: AtomicBool = AtomicBool::new(false);
+static TO_STDERR: AtomicBool = AtomicBool::new(false);
+
+/// Stream the rain is drawn on, the other one stays free for data.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum RenderTarget {
+  #[default]
+  Stdout,
+  Stderr,
+}
+
+impl Display for RenderTarget {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    f.write_str(match self {
+      RenderTarget::Stdout => "stdout",
+      RenderTarget::Stderr => "stderr",
+    })
+  }
+}
+
+impl FromStr for RenderTarget {
+  type Err = anyhow::Error;
+
+  fn from_str(s: &str) -> anyhow::Result<Self> {
+    match s {
+      "stdout" => Ok(RenderTarget::Stdout),
+      "stderr" => Ok(RenderTarget::Stderr),
+      _ => anyhow::bail!("expected `stdout` or `stderr`"),
+    }
+  }
+}
+
+/// Writer of the [`RenderTarget`] the guard was set up for.
+pub enum Output {
+  Stdout(Stdout),
+  /// Stderr is unbuffered, frames are collected and written on flush.
+  Stderr(BufWriter<Stderr>),
+}
+
+impl Write for Output {
+  fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+    match self {
+      Output::Stdout(out) => out.write(buf),
+      Output::Stderr(out) => out.write(buf),
+    }
+  }
+
+  fn flush(&mut self) -> std::io::Result<()> {
+    match self {
+      Output::Stdout(out) => out.flush(),
+      Output::Stderr(out) => out.flush(),
+    }
+  }
+}
+
+/// The stream the rain is drawn on, see [`TerminalGuard::new`].
+pub fn output() -> Output {
+  if TO_STDERR.load(Ordering::SeqCst) {
+    Output::Stderr(BufWriter::new(stderr()))
+  } else {
+    Output::Stdout(stdout())
+  }
+}
+
+/// Puts the terminal into raw drawing mode and restores it when dropped,
+/// including when the program panics.
+pub struct TerminalGuard;
+
+impl TerminalGuard {
+  /// With `alt_screen` the rain is drawn on the alternate screen buffer, leaving the
+  /// user's scrollback untouched. Every terminal command goes to `target`, as does
+  /// everything drawn through [`output`].
+  pub fn new(alt_screen: bool, target: RenderTarget) -> anyhow::Result<Self> {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+      // leave the alternate screen first so the panic message ends up on the main one
+      restore();
+      default_hook(info);
+    }));
+
+    ALT_SCREEN.store(alt_screen, Ordering::SeqCst);
+    TO_STDERR.store(target == RenderTarget::Stderr, Ordering::SeqCst);
+    ACTIVE.store(true, Ordering::SeqCst);
+
+    terminal::enable_raw_mode()?;
+    if alt_screen {
+      execute!(output(), EnterAlternateScreen)?;
+    }
+    // every print is preceded by a `MoveTo`, but with autowrap on a glyph in the last
+    // column wraps and one in the bottom right corner can scroll the whole screen up
+    execute!(
+      output(),
+      DisableLineWrap,
+      EnableFocusChange,
+      Clear(ClearType::All),
+      cursor::Hide,
+      MoveTo(0, 0)
+    )?;
+
+    Ok(Self)
+  }
+}
+
+impl Drop for TerminalGuard {
+  fn drop(&mut self) {
+    restore();
+  }
+}
+
+/// Best effort, errors are ignored since this runs on the way out.
+fn restore() {
+  if !ACTIVE.swap(false, Ordering::SeqCst) {
+    return;
+  }
+
+  let mut out = output();
+  let _ = execute!(
+    out,
+    EnableLineWrap,
+    DisableFocusChange,
+    ResetColor,
+    cursor::Show
+  );
+  let _ = if ALT_SCREEN.load(Ordering::SeqCst) {
+    execute!(out, LeaveAlternateScreen)
+  } else {
+    execute!(out, Clear(ClearType::All), MoveTo(0, 0))
+  };
+  let _ = terminal::disable_raw_mode();
+}
@@ -0,0 +1,99 @@
+/// A named look, a bundle of settings applied through [`Config::set`](crate::Config::set)
+/// like the CLI flags they mirror.
+pub struct Theme {
+  pub name: &'static str,
+  pub settings: &'static [(&'static str, &'static str)],
+}
+
+/// Built-in themes. Each one sets the same keys, so switching from one to another
+/// leaves nothing of the previous look behind.
+pub const THEMES: &[Theme] = &[
+  Theme {
+    name: "classic-green",
+    settings: &[
+      ("style", "solid:00ff41"),
+      ("glyphs", "matrix"),
+      ("fade-curve", "linear"),
+      ("head-length", "1"),
+      ("accent-rate", "0"),
+      ("vignette", "0"),
+      ("temperature", "0"),
+      ("head-color", "ffffff"),
+      ("background", "reset"),
+    ],
+  },
+  Theme {
+    name: "amber-terminal",
+    settings: &[
+      ("style", "solid:ffb000"),
+      ("chars", "0123456789ABCDEF"),
+      ("fade-curve", "quadratic"),
+      ("head-length", "1"),
+      ("accent-rate", "0"),
+      ("vignette", "0.3"),
+      ("temperature", "0.2"),
+      ("head-color", "fff2d0"),
+      ("background", "140c00"),
+    ],
+  },
+  Theme {
+    name: "ice-blue",
+    settings: &[
+      ("style", "solid:7fdbff"),
+      ("glyphs", "matrix"),
+      ("fade-curve", "exp"),
+      ("head-length", "2"),
+      ("accent", "ffffff"),
+      ("accent-rate", "0.01"),
+      ("vignette", "0.2"),
+      ("temperature", "-0.3"),
+      ("head-color", "ffffff"),
+      ("background", "000814"),
+    ],
+  },
+  Theme {
+    name: "rainbow",
+    settings: &[
+      ("style", "rainbow"),
+      ("glyphs", "matrix"),
+      ("fade-curve", "linear"),
+      ("head-length", "1"),
+      ("accent-rate", "0"),
+      ("vignette", "0"),
+      ("temperature", "0"),
+      ("head-color", "ffffff"),
+      ("background", "reset"),
+    ],
+  },
+  Theme {
+    name: "cyberpunk",
+    settings: &[
+      ("style", "shader:plasma"),
+      ("glyphs", "connectors"),
+      ("fade-curve", "exp"),
+      ("head-length", "2"),
+      ("accent", "00fff0"),
+      ("accent-rate", "0.03"),
+      ("vignette", "0.4"),
+      ("temperature", "0"),
+      ("head-color", "ff2bd6"),
+      ("background", "0d0221"),
+    ],
+  },
+];
+
+impl Theme {
+  /// Index of the theme called `name` in [`THEMES`].
+  pub fn position(name: &str) -> anyhow::Result<usize> {
+    THEMES
+      .iter()
+      .position(|theme| theme.name == name)
+      .ok_or_else(|| {
+        let names: Vec<_> = THEMES
+          .iter()
+          .map(|theme| format!("`{}`", theme.name))
+          .collect();
+        anyhow::anyhow!("expected one of {}", names.join(", "))
+      })
+  }
+}
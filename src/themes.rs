@@ -0,0 +1,60 @@
+use rmatrix::theme::{Theme, THEMES};
+
+/// Settings that pick the glyph source, setting any of them keeps every theme's glyphs out.
+const GLYPH_KEYS: &[&str] = &["chars", "charset", "glyphs", "words"];
+
+/// The theme picked with `--theme` and switched to at runtime. Settings the user gave
+/// are pinned: a theme is applied around them, wherever they were given.
+#[derive(Clone, Default)]
+pub struct Themes {
+  /// Index into [`THEMES`].
+  current: Option<usize>,
+  pinned: Vec<String>,
+}
+
+impl Themes {
+  pub fn select(&mut self, name: &str) -> anyhow::Result<()> {
+    self.current = Some(Theme::position(name)?);
+    Ok(())
+  }
+
+  /// Keeps themes from changing `key`.
+  pub fn pin(&mut self, key: &str) {
+    self.pinned.push(key.to_owned());
+  }
+
+  pub fn current(&self) -> Option<&'static Theme> {
+    self.current.map(|i| &THEMES[i])
+  }
+
+  fn is_pinned(&self, key: &str) -> bool {
+    let glyphs = GLYPH_KEYS.contains(&key);
+    self
+      .pinned
+      .iter()
+      .any(|pinned| pinned == key || glyphs && GLYPH_KEYS.contains(&pinned.as_str()))
+  }
+
+  /// Passes every setting of the current theme that isn't pinned to `set`.
+  pub fn apply(&self, mut set: impl FnMut(&str, &str) -> anyhow::Result<()>) -> anyhow::Result<()> {
+    let Some(theme) = self.current() else {
+      return Ok(());
+    };
+
+    for &(key, value) in theme.settings {
+      if !self.is_pinned(key) {
+        set(key, value)?;
+      }
+    }
+
+    Ok(())
+  }
+
+  /// Switches to the theme after the current one, or to the first, and applies it.
+  pub fn next(&mut self, set: impl FnMut(&str, &str) -> anyhow::Result<()>) -> anyhow::Result<()> {
+    let i = self.current.map_or(0, |i| (i + 1) % THEMES.len());
+    self.current = Some(i);
+    log::info!("switching to the {} theme", THEMES[i].name);
+    self.apply(set)
+  }
+}
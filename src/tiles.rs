@@ -0,0 +1,313 @@
+use crossterm::style::Color;
+use rmatrix::glyph::Glyph;
+use rmatrix::{Config, Frame, FrameCell, Rain};
+use std::time::Duration;
+
+/// Most tiles along either side of a [`Tiles`] wall, so tiles stay a few cells wide
+/// on a large terminal.
+pub const MAX_TILES: u16 = 16;
+
+/// How the tiles of a [`Tiles`] wall are set apart.
+#[derive(Clone, Copy, Debug)]
+pub struct TileLayout {
+  /// Blank columns and rows between neighbouring tiles.
+  pub gap: u16,
+  /// Color of a box-drawing border around every tile, none by default.
+  pub border: Option<Color>,
+}
+
+impl Default for TileLayout {
+  fn default() -> Self {
+    Self {
+      gap: 1,
+      border: None,
+    }
+  }
+}
+
+/// Independent rains side by side in a grid, each confined to its own tile with
+/// its own seed and optionally its own style. Tiles are set apart by a gap and optionally a border, see [`TileLayout`].
+pub struct Tiles {
+  columns: u16,
+  rows: u16,
+  width: u16,
+  height: u16,
+  layout: TileLayout,
+  rains: Vec<Rain>,
+}
+
+/// Start and length of tile `i` out of `count` along an axis `len` cells long, with
+/// `gap` cells between neighbouring tiles.
+fn span(i: u16, count: u16, len: u16, gap: u16) -> (u16, u16) {
+  // every tile but the last is followed by the gap
+  let len = len as u32 + gap as u32;
+  let start = i as u32 * len / count as u32;
+  let end = (i as u32 + 1) * len / count as u32;
+  (
+    start as u16,
+    (end - start).saturating_sub(gap as u32) as u16,
+  )
+}
+
+impl Tiles {
+  /// `columns` x `rows` tiles over a `width` x `height` screen, all sharing `config`
+  /// but for the `styles`, which the tiles take in turn. The tile seeds are derived
+  /// from the configured one, so a seeded wall is reproducible.
+  pub fn new(
+    config: &Config,
+    (columns, rows): (u16, u16),
+    layout: TileLayout,
+    styles: &[String],
+    width: u16,
+    height: u16,
+  ) -> Self {
+    let base: u64 = config.seed.unwrap_or_else(rand::random);
+    let mut tiles = Self {
+      columns,
+      rows,
+      width,
+      height,
+      layout,
+      rains: Vec::with_capacity(columns as usize * rows as usize),
+    };
+    tiles.warn_if_borderless();
+
+    for i in 0..columns as usize * rows as usize {
+      let (_, _, w, h) = tiles.inner(i);
+      let mut config = config.clone();
+      config.seed = Some(base ^ (i as u64).wrapping_mul(0x9E3779B97F4A7C15));
+      if !styles.is_empty() {
+        config
+          .set("style", &styles[i % styles.len()])
+          .expect("tile styles are checked when parsed");
+      }
+      tiles.rains.push(Rain::new(config, w, h));
+    }
+
+    tiles
+  }
+
+  /// Position and size of the tile of rain `i`, in row-major order.
+  fn rect(&self, i: usize) -> (u16, u16, u16, u16) {
+    let columns = self.columns as usize;
+    let (column, row) = ((i % columns) as u16, (i / columns) as u16);
+    let (x, w) = span(column, self.columns, self.width, self.layout.gap);
+    let (y, h) = span(row, self.rows, self.height, self.layout.gap);
+    (x, y, w, h)
+  }
+
+  /// Color of the borders, `None` without borders or while a tile is too small to
+  /// keep a cell of rain inside its border.
+  fn border(&self) -> Option<Color> {
+    let fits = (0..self.columns as usize * self.rows as usize).all(|i| {
+      let (_, _, w, h) = self.rect(i);
+      w > 2 && h > 2
+    });
+    self.layout.border.filter(|_| fits)
+  }
+
+  /// Logs when borders are set but left out, as the screen is too small for them.
+  fn warn_if_borderless(&self) {
+    if self.layout.border.is_some() && self.border().is_none() {
+      log::warn!(
+        "{}x{} is too small for borders around {}x{} tiles, leaving them out",
+        self.width,
+        self.height,
+        self.columns,
+        self.rows
+      );
+    }
+  }
+
+  /// Position and size of the rain of tile `i`: the tile within its border.
+  fn inner(&self, i: usize) -> (u16, u16, u16, u16) {
+    let (x, y, w, h) = self.rect(i);
+    match self.border() {
+      Some(_) => (x + 1, y + 1, w - 2, h - 2),
+      None => (x, y, w, h),
+    }
+  }
+
+  /// Re-tiles the screen, every rain is resized to its new tile.
+  pub fn resize(&mut self, width: u16, height: u16) {
+    (self.width, self.height) = (width, height);
+    self.warn_if_borderless();
+    for i in 0..self.rains.len() {
+      let (_, _, w, h) = self.inner(i);
+      self.rains[i].resize(w, h);
+    }
+  }
+
+  pub fn frame_delay(&self) -> Duration {
+    self.rains[0].frame_delay()
+  }
+
+  pub fn tick(&mut self) {
+    self.rains.iter_mut().for_each(Rain::tick);
+  }
+
+  pub fn shockwave(&mut self) {
+    self.rains.iter_mut().for_each(Rain::shockwave);
+  }
+
+  /// Changes a setting of every tile, see [`Rain::set`].
+  pub fn set(&mut self, key: &str, value: &str) -> anyhow::Result<()> {
+    for rain in &mut self.rains {
+      rain.set(key, value)?;
+    }
+    Ok(())
+  }
+
+  pub fn clear_canvas(&mut self) {
+    self.rains.iter_mut().for_each(Rain::clear_canvas);
+  }
+
+  pub fn set_focused(&mut self, focused: bool) {
+    for rain in &mut self.rains {
+      rain.set_focused(focused);
+    }
+  }
+
+  /// The frames of all rains composited into one of the screen's size.
+  pub fn compose(&self) -> Frame {
+    let mut frame = Frame::new(self.width, self.height);
+
+    if let Some(color) = self.border() {
+      for i in 0..self.rains.len() {
+        draw_box(&mut frame, self.rect(i), color);
+      }
+    }
+    for (i, rain) in self.rains.iter().enumerate() {
+      let (left, top, _, _) = self.inner(i);
+      for (y, row) in rain.render_frame().rows().enumerate() {
+        for (x, cell) in row.iter().enumerate() {
+          frame.set(left + x as u16, top + y as u16, cell.clone());
+        }
+      }
+    }
+
+    frame
+  }
+}
+
+/// Draws a box-drawing border along the edge of `rect`, at least 2x2 cells.
+fn draw_box(frame: &mut Frame, (x, y, w, h): (u16, u16, u16, u16), color: Color) {
+  let (right, bottom) = (x + w - 1, y + h - 1);
+  let mut put = |x, y, c: char| {
+    frame.set(
+      x,
+      y,
+      FrameCell {
+        glyph: Glyph::from(c),
+        fg: color,
+        bg: None,
+        bold: false,
+      },
+    )
+  };
+
+  for x in x + 1..right {
+    put(x, y, '─');
+    put(x, bottom, '─');
+  }
+  for y in y + 1..bottom {
+    put(x, y, '│');
+    put(right, y, '│');
+  }
+  put(x, y, '┌');
+  put(right, y, '┐');
+  put(x, bottom, '└');
+  put(right, bottom, '┘');
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn wall(grid: (u16, u16), styles: &[String], width: u16, height: u16) -> Tiles {
+    let mut config = Config::default();
+    config.seed = Some(6);
+    Tiles::new(&config, grid, TileLayout::default(), styles, width, height)
+  }
+
+  #[test]
+  fn tiles_are_apart_and_on_screen() {
+    for (width, height) in [(80, 24), (41, 13), (7, 5)] {
+      let tiles = wall((2, 2), &[], width, height);
+      let rects: Vec<_> = (0..4).map(|i| tiles.rect(i)).collect();
+      for &(x, y, w, h) in &rects {
+        assert!(
+          x + w <= width && y + h <= height,
+          "{:?} off {}x{}",
+          (x, y, w, h),
+          width,
+          height
+        );
+      }
+      // the gap of a cell between neighbours, across and down
+      let [top_left, top_right, bottom_left, _] = rects[..] else {
+        unreachable!()
+      };
+      assert_eq!(top_left.0 + top_left.2 + 1, top_right.0);
+      assert_eq!(top_left.1 + top_left.3 + 1, bottom_left.1);
+      for (i, a) in rects.iter().enumerate() {
+        for b in &rects[i + 1..] {
+          let apart = a.0 + a.2 < b.0 + 1
+            || b.0 + b.2 < a.0 + 1
+            || a.1 + a.3 < b.1 + 1
+            || b.1 + b.3 < a.1 + 1;
+          assert!(apart, "{:?} overlaps {:?} on {}x{}", a, b, width, height);
+        }
+      }
+
+      // nothing of the rains lands in the gaps
+      let mut tiles = tiles;
+      for _ in 0..20 {
+        tiles.tick();
+      }
+      let frame = tiles.compose();
+      assert_eq!((frame.width(), frame.height()), (width, height));
+      for (y, row) in frame.rows().enumerate() {
+        for (x, cell) in row.iter().enumerate() {
+          let (x, y) = (x as u16, y as u16);
+          let inside = rects
+            .iter()
+            .any(|&(left, top, w, h)| (left..left + w).contains(&x) && (top..top + h).contains(&y));
+          assert!(
+            inside || cell.glyph == Glyph::from(' '),
+            "a glyph in the gap at {},{}",
+            x,
+            y
+          );
+        }
+      }
+    }
+  }
+
+  #[test]
+  fn the_largest_wall_gives_every_tile_its_own_place() {
+    let tiles = wall((MAX_TILES, MAX_TILES), &[], 400, 200);
+    let count = MAX_TILES as usize * MAX_TILES as usize;
+    assert_eq!(tiles.rains.len(), count);
+    let mut rects: Vec<_> = (0..count).map(|i| tiles.rect(i)).collect();
+    rects.sort_unstable();
+    rects.dedup();
+    assert_eq!(rects.len(), count);
+  }
+
+  #[test]
+  fn tiles_take_the_styles_in_turn() {
+    let styles = ["rainbow".to_owned(), "solid:00ff41".to_owned()];
+    let tiles = wall((3, 1), &styles, 30, 10);
+    let style = |i: usize| {
+      let entries = tiles.rains[i].config().entries();
+      entries
+        .into_iter()
+        .find(|(key, _)| *key == "style")
+        .unwrap()
+        .1
+    };
+    assert_eq!(style(0), style(2));
+    assert_ne!(style(0), style(1));
+  }
+}
@@ -0,0 +1,82 @@
+use crossterm::event;
+use rand::Rng;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+/// Time left in the frame that `hybrid` pacing spins through instead of sleeping.
+/// Windows sleeps in ~15ms steps, elsewhere the scheduler is much finer.
+#[cfg(windows)]
+const SPIN_MARGIN: Duration = Duration::from_millis(16);
+#[cfg(not(windows))]
+const SPIN_MARGIN: Duration = Duration::from_millis(2);
+
+/// Longest wait between frames while nothing on screen moves.
+const IDLE_WAIT: Duration = Duration::from_millis(500);
+
+/// How the loop waits out the rest of a frame.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum Timing {
+  /// `thread::sleep`, cheap but only as precise as the OS timer.
+  #[default]
+  Sleep,
+  /// Sleeps for most of the frame, then spin-waits the remainder for precise pacing at the cost of CPU.
+  Hybrid,
+}
+
+impl Display for Timing {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    f.write_str(match self {
+      Timing::Sleep => "sleep",
+      Timing::Hybrid => "hybrid",
+    })
+  }
+}
+
+impl FromStr for Timing {
+  type Err = anyhow::Error;
+
+  fn from_str(s: &str) -> anyhow::Result<Self> {
+    match s {
+      "sleep" => Ok(Timing::Sleep),
+      "hybrid" => Ok(Timing::Hybrid),
+      _ => anyhow::bail!("expected `sleep` or `hybrid`"),
+    }
+  }
+}
+
+/// Waits up to [`IDLE_WAIT`] for the next input, instead of pacing frames nothing on
+/// screen changes in. Input ends the wait right away, so keys stay responsive.
+pub fn idle() -> anyhow::Result<()> {
+  event::poll(IDLE_WAIT)?;
+  Ok(())
+}
+
+/// `frame_budget` stretched or shrunk by a random share within `±jitter`, so frames
+/// don't tick with a mechanical rhythm. A `jitter` below 1 keeps it above zero.
+pub fn jittered(frame_budget: Duration, jitter: f32) -> Duration {
+  if jitter == 0.0 {
+    return frame_budget;
+  }
+
+  frame_budget.mul_f32(1.0 + rand::thread_rng().gen_range(-jitter..=jitter))
+}
+
+/// Waits until `frame_budget` has passed since `frame_start`.
+pub fn pace(timing: Timing, frame_budget: Duration, frame_start: Instant) {
+  let deadline = frame_start + frame_budget;
+  let remaining = deadline.saturating_duration_since(Instant::now());
+
+  match timing {
+    Timing::Sleep => sleep(remaining),
+    Timing::Hybrid => {
+      if remaining > SPIN_MARGIN {
+        sleep(remaining - SPIN_MARGIN);
+      }
+      while Instant::now() < deadline {
+        std::hint::spin_loop();
+      }
+    }
+  }
+}